@@ -1,9 +1,10 @@
 use crate::board;
 use crate::color;
-use crate::pieces::{piece, position};
+use crate::pieces::{piece, piece::Piece, position};
 
-pub type AttackedBoard<'a> = Vec<Vec<Vec<&'a Box<dyn piece::Piece>>>>;
+pub type AttackedBoard = Vec<Vec<Vec<piece::PlacedPiece>>>;
 
+#[allow(clippy::collapsible_match)]
 pub fn get_attacked_squares(
     board: &board::Board,
     color: color::Color,
@@ -27,7 +28,7 @@ pub fn get_attacked_squares(
                     attacked_positions.iter().for_each(|position| {
                         let attackers =
                             &mut attacked_board[position.0 as usize - 1][position.1 as usize - 1];
-                        attackers.push(piece);
+                        attackers.push(*piece);
                     })
                 }
             }
@@ -37,6 +38,165 @@ pub fn get_attacked_squares(
     attacked_board
 }
 
+// the squares of the pieces of `color` that attack `target`, found by looking outwards from it
+pub fn attackers(
+    board: &board::Board,
+    target: position::Position,
+    color: color::Color,
+) -> Vec<position::Position> {
+    let attackers = attacker_squares(board, target, color);
+    (0..64)
+        .filter(|index| attackers & 1 << index != 0)
+        .map(position::Position::from_bit_index)
+        .collect()
+}
+
+// like `attackers`, as a set of square bits so that move generation does not allocate
+pub(crate) fn attacker_squares(
+    board: &board::Board,
+    target: position::Position,
+    color: color::Color,
+) -> u64 {
+    let mut attackers = 0;
+    let square = |file: i8, rank: i8| {
+        if (1..=8).contains(&file) && (1..=8).contains(&rank) {
+            Some(position::Position(file as u8, rank as u8))
+        } else {
+            None
+        }
+    };
+    let is = |position: position::Position, pieces: &[piece::PieceEnum]| match board
+        .piece_at(position)
+    {
+        Some(found) => found.color == color && pieces.contains(&found.piece),
+        None => false,
+    };
+    let (file, rank) = (target.0 as i8, target.1 as i8);
+
+    // pawns attack forwards, so an attacking pawn stands one rank behind the target
+    let pawn_rank = match color {
+        color::Color::WHITE => -1,
+        color::Color::BLACK => 1,
+    };
+    let leapers: [(piece::PieceEnum, &[(i8, i8)]); 3] = [
+        (piece::PieceEnum::PAWN, &[(-1, pawn_rank), (1, pawn_rank)]),
+        (
+            piece::PieceEnum::KNIGHT,
+            &[
+                (1, 2),
+                (2, 1),
+                (2, -1),
+                (1, -2),
+                (-1, -2),
+                (-2, -1),
+                (-2, 1),
+                (-1, 2),
+            ],
+        ),
+        (
+            piece::PieceEnum::KING,
+            &[
+                (0, 1),
+                (1, 1),
+                (1, 0),
+                (1, -1),
+                (0, -1),
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+            ],
+        ),
+    ];
+    for (piece, offsets) in leapers.iter() {
+        for (file_offset, rank_offset) in offsets.iter() {
+            if let Some(position) = square(file + file_offset, rank + rank_offset) {
+                if is(position, &[*piece]) {
+                    attackers |= position.bit();
+                }
+            }
+        }
+    }
+
+    let straight = [piece::PieceEnum::ROOK, piece::PieceEnum::QUEEN];
+    let diagonal = [piece::PieceEnum::BISHOP, piece::PieceEnum::QUEEN];
+    let rays = [
+        ((0, 1), &straight),
+        ((1, 0), &straight),
+        ((0, -1), &straight),
+        ((-1, 0), &straight),
+        ((1, 1), &diagonal),
+        ((1, -1), &diagonal),
+        ((-1, -1), &diagonal),
+        ((-1, 1), &diagonal),
+    ];
+    for ((file_step, rank_step), sliders) in rays.iter() {
+        let mut current = square(file + file_step, rank + rank_step);
+        while let Some(position) = current {
+            if board.piece_at(position).is_some() {
+                if is(position, *sliders) {
+                    attackers |= position.bit();
+                }
+                break;
+            }
+            current = square(position.0 as i8 + file_step, position.1 as i8 + rank_step);
+        }
+    }
+
+    attackers
+}
+
+// for every piece of `color` pinned to its king on `king`, the squares it may still move to: the
+// line up to and including the pinning piece. Squares without a pinned piece are zero.
+pub(crate) fn pin_rays(
+    board: &board::Board,
+    king: position::Position,
+    color: color::Color,
+) -> [u64; 64] {
+    let mut pins = [0; 64];
+    let square = |file: i8, rank: i8| {
+        if (1..=8).contains(&file) && (1..=8).contains(&rank) {
+            Some(position::Position(file as u8, rank as u8))
+        } else {
+            None
+        }
+    };
+
+    let straight = [piece::PieceEnum::ROOK, piece::PieceEnum::QUEEN];
+    let diagonal = [piece::PieceEnum::BISHOP, piece::PieceEnum::QUEEN];
+    let rays = [
+        ((0, 1), &straight),
+        ((1, 0), &straight),
+        ((0, -1), &straight),
+        ((-1, 0), &straight),
+        ((1, 1), &diagonal),
+        ((1, -1), &diagonal),
+        ((-1, -1), &diagonal),
+        ((-1, 1), &diagonal),
+    ];
+    for ((file_step, rank_step), sliders) in rays.iter() {
+        let mut ray = 0;
+        let mut pinned = None;
+        let mut current = square(king.0 as i8 + file_step, king.1 as i8 + rank_step);
+        while let Some(position) = current {
+            ray |= position.bit();
+            match (board.piece_at(position), pinned) {
+                (None, _) => (),
+                (Some(found), None) if found.color == color => pinned = Some(position),
+                (Some(found), Some(pinned)) => {
+                    if found.color != color && sliders.contains(&found.piece) {
+                        pins[pinned.bit().trailing_zeros() as usize] = ray;
+                    }
+                    break;
+                }
+                (Some(_), None) => break,
+            }
+            current = square(position.0 as i8 + file_step, position.1 as i8 + rank_step);
+        }
+    }
+
+    pins
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::pieces::{pawn, piece, position};
@@ -77,6 +237,7 @@ mod tests {
         initial_board_attacks(color::Color::BLACK);
     }
 
+    #[allow(clippy::needless_range_loop, clippy::identity_op)]
     fn initial_board_attacks(color: color::Color) {
         let board = board::Board::initial();
         let attacked_squares = get_attacked_squares(&board, color, position::Position(0, 0));
@@ -196,8 +357,36 @@ mod tests {
         assert_attacked_by(&attacked_squares[7][0 + modifier], vec![]);
     }
 
+    #[test]
+    fn pin_rays_of_pinned_pieces() {
+        // the d2 knight is pinned by the a5 bishop and the e4 rook by the e8 queen. The g3 pawn is
+        // shielded from the h4 bishop by the f2 pawn, and the c1 rook only shields from a knight
+        let game =
+            crate::game::Game::from_fen("4q2k/8/8/b7/4R2b/6P1/3N1P2/1nR1K3 w - - 0 1").unwrap();
+        let king = position::Position(5, 1);
+        let pins = pin_rays(game.board(), king, color::Color::WHITE);
+        let squares = |squares: &[(u8, u8)]| {
+            squares.iter().fold(0, |acc, &(file, rank)| {
+                acc | position::Position(file, rank).bit()
+            })
+        };
+
+        assert_eq!(
+            squares(&[(4, 2), (3, 3), (2, 4), (1, 5)]),
+            pins[position::Position(4, 2).bit().trailing_zeros() as usize]
+        );
+        assert_eq!(
+            squares(&[(5, 2), (5, 3), (5, 4), (5, 5), (5, 6), (5, 7), (5, 8)]),
+            pins[position::Position(5, 4).bit().trailing_zeros() as usize]
+        );
+        assert_eq!(2, pins.iter().filter(|ray| **ray != 0).count());
+
+        let pins = pin_rays(game.board(), position::Position(8, 8), color::Color::BLACK);
+        assert!(pins.iter().all(|ray| *ray == 0));
+    }
+
     fn assert_attacked_by(
-        actual_attackers: &Vec<&std::boxed::Box<dyn piece::Piece>>,
+        actual_attackers: &[piece::PlacedPiece],
         attackers: Vec<(piece::PieceEnum, u8)>,
     ) {
         let expected_sum_of_attackers = attackers.iter().fold(0, |acc, attacker| acc + attacker.1);
@@ -0,0 +1,417 @@
+use super::{FILE_A, FILE_H, RANK_1, RANK_8};
+use std::sync::OnceLock;
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+// multipliers that map every relevant occupancy of a square to its own table entry, found
+// once with a seeded search over sparse random numbers
+const ROOK_MAGICS: [u64; 64] = [
+    0x2080002080400010,
+    0x00c0002001401000,
+    0x2100110008402002,
+    0x0880080081041000,
+    0x0200020020041008,
+    0x2300040008010012,
+    0x0c00283004008201,
+    0x0180010000407a80,
+    0x0168800080400020,
+    0x0010400040201000,
+    0x1001002001001048,
+    0x1001002408100100,
+    0x0801000408010012,
+    0x4001000209000400,
+    0x08a20004c8020001,
+    0x2002801145002280,
+    0x0080860021004200,
+    0x001000c009402002,
+    0x00b0002004002800,
+    0x100a808010020800,
+    0x8101010008000410,
+    0x0244008002000480,
+    0x0000040010810208,
+    0x2000020000448534,
+    0x4104400480008033,
+    0x0000810100204000,
+    0x0440430900200010,
+    0x4600240900100100,
+    0x0060080080040080,
+    0x0001000300080400,
+    0x0004084400011002,
+    0x0023040200008041,
+    0x0580050043002080,
+    0x0400804002802008,
+    0x0001002001004010,
+    0x1000200901001000,
+    0x4410800801800c00,
+    0xa012003806001004,
+    0x0020100104008802,
+    0x0004808402000041,
+    0x0010400170898000,
+    0x0080500020004004,
+    0x1040408012020020,
+    0x8010040008004040,
+    0x2001080100110004,
+    0x0000020004008080,
+    0x0021010810040002,
+    0x0800008c43020024,
+    0x0000800021005100,
+    0x0070201040008080,
+    0x0000d04282006a00,
+    0x0010014400080240,
+    0x0001080110050100,
+    0x0012000810240600,
+    0x0402000801040200,
+    0x028100108a004100,
+    0x0050800300102045,
+    0x8208210040120882,
+    0x8010600101183441,
+    0x020b000910006045,
+    0x0241001002480005,
+    0x0081000400880241,
+    0x0000009008024124,
+    0x0048122980410402,
+];
+
+const BISHOP_MAGICS: [u64; 64] = [
+    0x0848020822040013,
+    0x8010a40085821200,
+    0x0008008430840822,
+    0x0808048108040000,
+    0x1304042100008104,
+    0x5001012010204023,
+    0x81048801b8200420,
+    0x200a008084012000,
+    0x0040102001042084,
+    0x840a505042428020,
+    0x0000700102202920,
+    0x44101c0c10800002,
+    0x0040040422000000,
+    0x0180020802090202,
+    0x4020020811041202,
+    0x000104308c042000,
+    0x4140661002424400,
+    0x0028012008010460,
+    0x0188062102002a00,
+    0x0014004840102008,
+    0x0105000290400002,
+    0x8001022200410400,
+    0x104a041918013446,
+    0x008a000082008238,
+    0x04a0060008100430,
+    0x0008220008820801,
+    0x2508041208005010,
+    0x4008080200202020,
+    0x2441001013004000,
+    0x0030008060407000,
+    0x4008108000420800,
+    0x0012021050290100,
+    0x0210080482200500,
+    0xcc01112048100480,
+    0x0020402806500440,
+    0x00048e0080580080,
+    0x0040102020020080,
+    0x0028010440080807,
+    0x4601041108008800,
+    0x8040810e04104200,
+    0x901210110400088a,
+    0xa003080212081050,
+    0x00c1004048401004,
+    0x900000a014400800,
+    0x0008021040405401,
+    0x4020008206002090,
+    0x0004190424030100,
+    0x0424008a02026250,
+    0x8004088250900040,
+    0x1c00430088a04200,
+    0x0001020094040001,
+    0x8040210020880061,
+    0x2010040450442032,
+    0x0800840850044001,
+    0x0004040802140004,
+    0x0004080a04222020,
+    0x8088802110022000,
+    0x1081a10416114400,
+    0x0205010a24060820,
+    0x0000000720411080,
+    0x1008000208430400,
+    0x580c026028810840,
+    0x802020441020a110,
+    0x12c0022401020018,
+];
+
+// looks up the attacks of a slider for every occupancy of the squares it could be blocked on
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: u64) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+pub(crate) struct Tables {
+    knight: [u64; 64],
+    king: [u64; 64],
+    pawn: [[u64; 64]; 2],
+    rook_magics: Vec<Magic>,
+    bishop_magics: Vec<Magic>,
+    sliding: Vec<u64>,
+    between: Vec<u64>,
+    line: Vec<u64>,
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+pub(crate) fn tables() -> &'static Tables {
+    TABLES.get_or_init(Tables::new)
+}
+
+impl Tables {
+    fn new() -> Self {
+        let mut knight = [0; 64];
+        let mut king = [0; 64];
+        let mut pawn = [[0; 64]; 2];
+        for square in 0..64 {
+            knight[square] = steps(
+                square,
+                &[
+                    (1, 2),
+                    (2, 1),
+                    (2, -1),
+                    (1, -2),
+                    (-1, -2),
+                    (-2, -1),
+                    (-2, 1),
+                    (-1, 2),
+                ],
+            );
+            king[square] = steps(
+                square,
+                &[
+                    (1, 0),
+                    (1, 1),
+                    (0, 1),
+                    (-1, 1),
+                    (-1, 0),
+                    (-1, -1),
+                    (0, -1),
+                    (1, -1),
+                ],
+            );
+            pawn[0][square] = steps(square, &[(-1, 1), (1, 1)]);
+            pawn[1][square] = steps(square, &[(-1, -1), (1, -1)]);
+        }
+
+        let mut sliding = vec![];
+        let rook_magics = (0..64)
+            .map(|square| build_magic(square, &ROOK_DIRECTIONS, ROOK_MAGICS[square], &mut sliding))
+            .collect();
+        let bishop_magics = (0..64)
+            .map(|square| {
+                build_magic(
+                    square,
+                    &BISHOP_DIRECTIONS,
+                    BISHOP_MAGICS[square],
+                    &mut sliding,
+                )
+            })
+            .collect();
+
+        let mut between = vec![0; 64 * 64];
+        let mut line = vec![0; 64 * 64];
+        for from in 0..64 {
+            for direction in ROOK_DIRECTIONS.iter().chain(BISHOP_DIRECTIONS.iter()) {
+                let full_line =
+                    slide(from, &[*direction, (-direction.0, -direction.1)], 0) | 1 << from;
+                let mut squares_between = 0;
+                let mut current = from;
+                while let Some(to) = offset(current, *direction) {
+                    between[from * 64 + to] = squares_between;
+                    line[from * 64 + to] = full_line;
+                    squares_between |= 1 << to;
+                    current = to;
+                }
+            }
+        }
+
+        Self {
+            knight,
+            king,
+            pawn,
+            rook_magics,
+            bishop_magics,
+            sliding,
+            between,
+            line,
+        }
+    }
+
+    pub(crate) fn knight(&self, square: usize) -> u64 {
+        self.knight[square]
+    }
+
+    pub(crate) fn king(&self, square: usize) -> u64 {
+        self.king[square]
+    }
+
+    // squares attacked by a pawn of `color` (0 white, 1 black) standing on `square`
+    pub(crate) fn pawn(&self, color: usize, square: usize) -> u64 {
+        self.pawn[color][square]
+    }
+
+    pub(crate) fn rook(&self, square: usize, occupied: u64) -> u64 {
+        self.sliding[self.rook_magics[square].index(occupied)]
+    }
+
+    pub(crate) fn bishop(&self, square: usize, occupied: u64) -> u64 {
+        self.sliding[self.bishop_magics[square].index(occupied)]
+    }
+
+    // squares strictly between two squares on a common line, empty if there is none
+    pub(crate) fn between(&self, from: usize, to: usize) -> u64 {
+        self.between[from * 64 + to]
+    }
+
+    // the whole line through both squares from edge to edge, empty if there is none
+    pub(crate) fn line(&self, from: usize, to: usize) -> u64 {
+        self.line[from * 64 + to]
+    }
+}
+
+fn build_magic(
+    square: usize,
+    directions: &[(i8, i8)],
+    magic: u64,
+    sliding: &mut Vec<u64>,
+) -> Magic {
+    // the outermost square of a ray never blocks anything behind it
+    let edges = ((RANK_1 | RANK_8) & !rank_of(square)) | ((FILE_A | FILE_H) & !file_of(square));
+    let mask = slide(square, directions, 0) & !edges;
+    let bits = mask.count_ones();
+
+    let magic = Magic {
+        mask,
+        magic,
+        shift: 64 - bits,
+        offset: sliding.len(),
+    };
+    sliding.resize(sliding.len() + (1 << bits), 0);
+
+    // visits every subset of the mask
+    let mut occupied: u64 = 0;
+    loop {
+        let attacks = slide(square, directions, occupied);
+        let entry = &mut sliding[magic.index(occupied)];
+        if *entry != 0 && *entry != attacks {
+            panic!(
+                "Magic number for square {} maps occupancies together",
+                square
+            );
+        }
+        *entry = attacks;
+
+        occupied = occupied.wrapping_sub(mask) & mask;
+        if occupied == 0 {
+            break;
+        }
+    }
+
+    magic
+}
+
+fn file_of(square: usize) -> u64 {
+    FILE_A << (square % 8)
+}
+
+fn rank_of(square: usize) -> u64 {
+    RANK_1 << (8 * (square / 8))
+}
+
+fn offset(square: usize, (file, rank): (i8, i8)) -> Option<usize> {
+    let file = (square % 8) as i8 + file;
+    let rank = (square / 8) as i8 + rank;
+    if (0..8).contains(&file) && (0..8).contains(&rank) {
+        Some((rank * 8 + file) as usize)
+    } else {
+        None
+    }
+}
+
+fn steps(square: usize, steps: &[(i8, i8)]) -> u64 {
+    steps
+        .iter()
+        .filter_map(|step| offset(square, *step))
+        .fold(0, |bitboard, to| bitboard | 1 << to)
+}
+
+// walks each direction until the edge or the first occupied square, which is included
+fn slide(square: usize, directions: &[(i8, i8)], occupied: u64) -> u64 {
+    let mut attacks = 0;
+    for direction in directions {
+        let mut current = square;
+        while let Some(next) = offset(current, *direction) {
+            attacks |= 1 << next;
+            if occupied & 1 << next != 0 {
+                break;
+            }
+            current = next;
+        }
+    }
+    attacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_lookups_match_ray_walks() {
+        let tables = tables();
+        let mut random: u64 = 42;
+        for square in 0..64 {
+            for _ in 0..64 {
+                // xorshift64
+                random ^= random << 13;
+                random ^= random >> 7;
+                random ^= random << 17;
+                let occupied = random & random.rotate_left(29);
+                assert_eq!(
+                    slide(square, &ROOK_DIRECTIONS, occupied),
+                    tables.rook(square, occupied)
+                );
+                assert_eq!(
+                    slide(square, &BISHOP_DIRECTIONS, occupied),
+                    tables.bishop(square, occupied)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn leaper_tables() {
+        let tables = tables();
+        // a1 and e4
+        assert_eq!(1 << 10 | 1 << 17, tables.knight(0));
+        assert_eq!(8, tables.knight(28).count_ones());
+        assert_eq!(3, tables.king(0).count_ones());
+        assert_eq!(1 << 19 | 1 << 21, tables.pawn(0, 12));
+        assert_eq!(1 << 3 | 1 << 5, tables.pawn(1, 12));
+        assert_eq!(0, tables.pawn(0, 56));
+    }
+
+    #[test]
+    fn between_and_line() {
+        let tables = tables();
+        // a1 to d4 and a1 to b3
+        assert_eq!(1 << 9 | 1 << 18, tables.between(0, 27));
+        assert_eq!(0, tables.between(0, 17));
+        assert_eq!(0, tables.line(0, 17));
+        assert_eq!(8, tables.line(0, 27).count_ones());
+        assert_eq!(tables.line(0, 27), tables.line(27, 0));
+    }
+}
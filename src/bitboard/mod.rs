@@ -0,0 +1,420 @@
+mod attacks;
+
+use crate::pieces::{piece, position};
+use crate::{chessmove, color, game, square};
+
+// square indices run a1 = 0, b1 = 1, ..., h8 = 63, the same order `Board` uses
+pub(crate) const FILE_A: u64 = 0x0101_0101_0101_0101;
+pub(crate) const FILE_H: u64 = FILE_A << 7;
+pub(crate) const RANK_1: u64 = 0xff;
+pub(crate) const RANK_8: u64 = RANK_1 << 56;
+
+const PAWN: usize = 0;
+const KNIGHT: usize = 1;
+const BISHOP: usize = 2;
+const ROOK: usize = 3;
+const QUEEN: usize = 4;
+const KING: usize = 5;
+
+const WHITE: usize = 0;
+const BLACK: usize = 1;
+
+// iterates over the squares of a bitboard, lowest first
+struct Squares(u64);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+fn square_index(position: position::Position) -> usize {
+    (position.1 as usize - 1) * 8 + position.0 as usize - 1
+}
+
+fn square_coordinates(square: usize) -> (u8, u8) {
+    (square as u8 % 8 + 1, square as u8 / 8 + 1)
+}
+
+// a position as one bitboard per colour and piece, used to generate moves with table lookups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bitboards {
+    pieces: [[u64; 6]; 2],
+    colors: [u64; 2],
+    side_to_move: usize,
+    // kingside and queenside, per colour
+    castling_rights: [(bool, bool); 2],
+    en_passant: Option<usize>,
+}
+
+impl Bitboards {
+    pub fn from_game(game: &game::Game) -> Self {
+        let mut pieces = [[0; 6]; 2];
+        let mut colors = [0; 2];
+
+        for file in 1..=8 {
+            for rank in 1..=8 {
+                let position = position::Position(file, rank);
+                if let Some(colored_piece) = game.board().piece_at(position) {
+                    let color = color_index(colored_piece.color);
+                    let piece = match colored_piece.piece {
+                        piece::PieceEnum::PAWN => PAWN,
+                        piece::PieceEnum::KNIGHT => KNIGHT,
+                        piece::PieceEnum::BISHOP => BISHOP,
+                        piece::PieceEnum::ROOK => ROOK,
+                        piece::PieceEnum::QUEEN => QUEEN,
+                        piece::PieceEnum::KING => KING,
+                    };
+                    pieces[color][piece] |= 1 << square_index(position);
+                    colors[color] |= 1 << square_index(position);
+                }
+            }
+        }
+
+        Self {
+            pieces,
+            colors,
+            side_to_move: color_index(*game.side_to_move()),
+            castling_rights: [game.castling_rights_white(), game.castling_rights_black()],
+            en_passant: game.en_passant().map(square_index),
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<chessmove::ChessMove> {
+        let mut moves = chessmove::MoveList::new();
+        self.generate_legal_moves(&mut moves);
+        moves.to_vec()
+    }
+
+    // pushes the legal moves onto `moves`
+    pub fn generate_legal_moves(&self, moves: &mut chessmove::MoveList) {
+        self.generate_moves_into(moves);
+    }
+
+    // returns the squares of the pieces giving check, which the generator finds anyway
+    pub(crate) fn generate_moves_into(&self, moves: &mut impl chessmove::MoveSink) -> u64 {
+        let tables = attacks::tables();
+        let us = self.side_to_move;
+        let them = 1 - us;
+        let occupied = self.colors[WHITE] | self.colors[BLACK];
+        let king = self.pieces[us][KING].trailing_zeros() as usize;
+
+        let checkers = self.attackers(king, occupied, self.colors[them]);
+        let pinned = self.pinned(king);
+
+        let king_bit = 1u64 << king;
+        for to in Squares(tables.king(king) & !self.colors[us]) {
+            if self.attackers(to, occupied ^ king_bit, self.colors[them]) == 0 {
+                self.add(
+                    moves,
+                    new_move(king, to, None),
+                    KING,
+                    chessmove::MoveKind::Normal,
+                );
+            }
+        }
+
+        if checkers.count_ones() > 1 {
+            return checkers;
+        }
+
+        let check_mask = match checkers {
+            0 => {
+                self.add_castling_moves(king, occupied, moves);
+                !0
+            }
+            checker => tables.between(king, checker.trailing_zeros() as usize) | checker,
+        };
+
+        for piece in &[KNIGHT, BISHOP, ROOK, QUEEN] {
+            for from in Squares(self.pieces[us][*piece] & !pinned) {
+                let targets = self.attacks(*piece, from, occupied) & !self.colors[us] & check_mask;
+                for to in Squares(targets) {
+                    let mv = new_move(from, to, None);
+                    self.add(moves, mv, *piece, chessmove::MoveKind::Normal);
+                }
+            }
+            // a pinned knight can never move, a pinned slider only along the pin
+            for from in Squares(self.pieces[us][*piece] & pinned) {
+                let targets = self.attacks(*piece, from, occupied)
+                    & !self.colors[us]
+                    & check_mask
+                    & tables.line(king, from);
+                for to in Squares(targets) {
+                    let mv = new_move(from, to, None);
+                    self.add(moves, mv, *piece, chessmove::MoveKind::Normal);
+                }
+            }
+        }
+
+        self.add_pawn_moves(king, occupied, check_mask, pinned, moves);
+        checkers
+    }
+
+    fn add_pawn_moves(
+        &self,
+        king: usize,
+        occupied: u64,
+        check_mask: u64,
+        pinned: u64,
+        moves: &mut impl chessmove::MoveSink,
+    ) {
+        let tables = attacks::tables();
+        let us = self.side_to_move;
+        let them = 1 - us;
+        let (forward, start_rank, last_rank): (i8, u64, u64) = match us {
+            WHITE => (8, RANK_1 << 8, RANK_8),
+            _ => (-8, RANK_8 >> 8, RANK_1),
+        };
+
+        for from in Squares(self.pieces[us][PAWN]) {
+            let pin_mask = if pinned & 1 << from != 0 {
+                tables.line(king, from)
+            } else {
+                !0
+            };
+
+            let mut targets = tables.pawn(us, from) & self.colors[them];
+            let one_step = (from as i8 + forward) as usize;
+            let mut double_push = 0;
+            if occupied & 1 << one_step == 0 {
+                targets |= 1 << one_step;
+                let two_steps = (one_step as i8 + forward) as usize;
+                if start_rank & 1 << from != 0 && occupied & 1 << two_steps == 0 {
+                    double_push = 1 << two_steps;
+                    targets |= double_push;
+                }
+            }
+
+            for to in Squares(targets & check_mask & pin_mask) {
+                if last_rank & 1 << to != 0 {
+                    for promotion in piece::PROMOTION_PIECES.iter() {
+                        let mv = new_move(from, to, Some(*promotion));
+                        self.add(moves, mv, PAWN, chessmove::MoveKind::Promotion);
+                    }
+                } else if double_push & 1 << to != 0 {
+                    let mv = new_move(from, to, None);
+                    self.add(moves, mv, PAWN, chessmove::MoveKind::DoublePawnPush);
+                } else {
+                    let mv = new_move(from, to, None);
+                    self.add(moves, mv, PAWN, chessmove::MoveKind::Normal);
+                }
+            }
+
+            if let Some(en_passant) = self.en_passant {
+                if tables.pawn(us, from) & 1 << en_passant != 0
+                    && self.en_passant_is_legal(king, from, en_passant, occupied)
+                {
+                    let mv = new_move(from, en_passant, None);
+                    self.add(moves, mv, PAWN, chessmove::MoveKind::EnPassant);
+                }
+            }
+        }
+    }
+
+    // en passant removes two pieces from a line at once, so the resulting position is checked
+    fn en_passant_is_legal(&self, king: usize, from: usize, to: usize, occupied: u64) -> bool {
+        let captured = match self.side_to_move {
+            WHITE => to - 8,
+            _ => to + 8,
+        };
+        let occupied = occupied ^ 1 << from ^ 1 << captured | 1 << to;
+        let enemies = self.colors[1 - self.side_to_move] & !(1 << captured);
+        self.attackers(king, occupied, enemies) == 0
+    }
+
+    fn add_castling_moves(&self, king: usize, occupied: u64, moves: &mut impl chessmove::MoveSink) {
+        let them = self.colors[1 - self.side_to_move];
+        let safe = |square: usize| self.attackers(square, occupied, them) == 0;
+        let empty = |square: usize| occupied & 1 << square == 0;
+        let (kingside, queenside) = self.castling_rights[self.side_to_move];
+
+        if kingside
+            && king % 8 <= 5
+            && empty(king + 1)
+            && empty(king + 2)
+            && safe(king + 1)
+            && safe(king + 2)
+        {
+            let mv = new_move(king, king + 2, None);
+            self.add(moves, mv, KING, chessmove::MoveKind::CastleKingside);
+        }
+        if queenside
+            && king % 8 >= 3
+            && empty(king - 1)
+            && empty(king - 2)
+            && empty(king - 3)
+            && safe(king - 1)
+            && safe(king - 2)
+        {
+            let mv = new_move(king, king - 2, None);
+            self.add(moves, mv, KING, chessmove::MoveKind::CastleQueenside);
+        }
+    }
+
+    // the captured piece is looked up only if `moves` keeps the details
+    fn add(
+        &self,
+        moves: &mut impl chessmove::MoveSink,
+        mv: chessmove::ChessMove,
+        piece: usize,
+        kind: chessmove::MoveKind,
+    ) {
+        moves.add(mv, || {
+            let to = square_index(position::Position(mv.to.0, mv.to.1));
+            let captured = match kind {
+                chessmove::MoveKind::EnPassant => match self.side_to_move {
+                    WHITE => Some((PAWN, to - 8)),
+                    _ => Some((PAWN, to + 8)),
+                },
+                _ => (PAWN..=KING)
+                    .find(|piece| self.pieces[1 - self.side_to_move][*piece] & 1 << to != 0)
+                    .map(|piece| (piece, to)),
+            };
+            chessmove::MoveInfo {
+                chessmove: mv,
+                piece: piece_enum(piece),
+                captured: captured.and_then(|(piece, index)| {
+                    Some((piece_enum(piece), square::Square::new(index as u8)?))
+                }),
+                kind,
+            }
+        });
+    }
+
+    fn attacks(&self, piece: usize, from: usize, occupied: u64) -> u64 {
+        let tables = attacks::tables();
+        match piece {
+            KNIGHT => tables.knight(from),
+            BISHOP => tables.bishop(from, occupied),
+            ROOK => tables.rook(from, occupied),
+            _ => tables.bishop(from, occupied) | tables.rook(from, occupied),
+        }
+    }
+
+    // pieces among `by` that attack `square`, given the occupied squares
+    fn attackers(&self, square: usize, occupied: u64, by: u64) -> u64 {
+        let tables = attacks::tables();
+        let pieces =
+            |piece: usize| (self.pieces[WHITE][piece] | self.pieces[BLACK][piece]) & by & occupied;
+
+        (tables.pawn(self.side_to_move, square) & pieces(PAWN))
+            | (tables.knight(square) & pieces(KNIGHT))
+            | (tables.king(square) & pieces(KING))
+            | (tables.bishop(square, occupied) & (pieces(BISHOP) | pieces(QUEEN)))
+            | (tables.rook(square, occupied) & (pieces(ROOK) | pieces(QUEEN)))
+    }
+
+    // pieces of the side to move that are the only blocker between their king and a slider
+    fn pinned(&self, king: usize) -> u64 {
+        let tables = attacks::tables();
+        let us = self.side_to_move;
+        let them = 1 - us;
+        let occupied = self.colors[WHITE] | self.colors[BLACK];
+
+        let snipers = (tables.rook(king, self.colors[them])
+            & (self.pieces[them][ROOK] | self.pieces[them][QUEEN]))
+            | (tables.bishop(king, self.colors[them])
+                & (self.pieces[them][BISHOP] | self.pieces[them][QUEEN]));
+
+        Squares(snipers)
+            .map(|sniper| tables.between(king, sniper) & occupied)
+            .filter(|blockers| blockers.count_ones() == 1)
+            .fold(0, |pinned, blockers| pinned | (blockers & self.colors[us]))
+    }
+}
+
+fn color_index(color: color::Color) -> usize {
+    match color {
+        color::Color::WHITE => WHITE,
+        color::Color::BLACK => BLACK,
+    }
+}
+
+fn piece_enum(piece: usize) -> piece::PieceEnum {
+    match piece {
+        PAWN => piece::PieceEnum::PAWN,
+        KNIGHT => piece::PieceEnum::KNIGHT,
+        BISHOP => piece::PieceEnum::BISHOP,
+        ROOK => piece::PieceEnum::ROOK,
+        QUEEN => piece::PieceEnum::QUEEN,
+        _ => piece::PieceEnum::KING,
+    }
+}
+
+fn new_move(
+    from: usize,
+    to: usize,
+    promotion: Option<piece::PromotionPiece>,
+) -> chessmove::ChessMove {
+    chessmove::ChessMove {
+        from: square_coordinates(from),
+        to: square_coordinates(to),
+        promotion,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITIONS: [&str; 9] = [
+        crate::fen::INITIAL_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        // en passant would expose the king along the rank
+        "8/8/8/KPp4r/8/8/8/7k w - c6 0 1",
+        // en passant removes the checking pawn
+        "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+        // double check
+        "4k3/8/8/8/8/5n2/8/r3K3 w - - 0 1",
+    ];
+
+    fn sorted(moves: Vec<chessmove::ChessMove>) -> Vec<String> {
+        let mut moves = moves.iter().map(|mv| mv.to_string()).collect::<Vec<_>>();
+        moves.sort();
+        moves
+    }
+
+    // compares against the generator working on `Board`, for every position two plies deep
+    fn compare(game: &mut game::Game, depth: u8) {
+        let expected = game.legal_moves_from_board();
+        assert_eq!(
+            sorted(expected.clone()),
+            sorted(Bitboards::from_game(game).legal_moves()),
+            "{}",
+            game.to_fen()
+        );
+
+        let mut details = game.legal_moves_detailed();
+        let mut expected_details = game.legal_moves_detailed_from_board();
+        details.sort_by_key(|info| info.chessmove.to_string());
+        expected_details.sort_by_key(|info| info.chessmove.to_string());
+        assert_eq!(expected_details, details, "{}", game.to_fen());
+
+        if depth > 1 {
+            for mv in expected {
+                game.make_move(mv);
+                compare(game, depth - 1);
+                game.undo_last_move();
+            }
+        }
+    }
+
+    #[test]
+    fn same_moves_as_board_generator() {
+        for fen in POSITIONS.iter() {
+            compare(&mut game::Game::from_fen(fen).unwrap(), 2);
+        }
+    }
+}
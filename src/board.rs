@@ -1,202 +1,191 @@
-use super::pieces::to_piece::ToPiece;
-use super::pieces::{bishop, king, knight, pawn, piece, position, queen, rook};
+use super::pieces::{piece, position};
 
-use crate::color;
-use std::slice;
+use crate::{color, display, error, square};
+use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Board {
-    board: Vec<Vec<Option<Box<dyn piece::Piece>>>>,
+    // a1, b1, ..., h1, a2, ..., h8
+    squares: [Option<piece::ColoredPiece>; 64],
+}
+
+fn index(position: position::Position) -> usize {
+    (position.1 as usize - 1) * 8 + position.0 as usize - 1
+}
+
+// ASCII letters seen from White's side, `display::BoardDisplay` has the other options
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", display::BoardDisplay::new(self))
+    }
 }
 
 impl Board {
     pub fn empty() -> Self {
-        let mut empty_board: Vec<Vec<Option<Box<dyn piece::Piece>>>> = vec![];
-        for _ in 0..8 {
-            let mut empty_file: Vec<Option<Box<dyn piece::Piece>>> = vec![];
-            for _ in 0..8 {
-                empty_file.push(None);
-            }
-            empty_board.push(empty_file);
+        Self {
+            squares: [None; 64],
         }
-        Self { board: empty_board }
     }
 
     pub fn initial() -> Self {
-        let mut board: Vec<Vec<Option<Box<dyn piece::Piece>>>> = vec![];
-        for _ in 0..8 {
-            let mut empty_file: Vec<Option<Box<dyn piece::Piece>>> = vec![];
-            for _ in 0..8 {
-                empty_file.push(None);
-            }
-            board.push(empty_file);
-        }
+        let mut board = Self::empty();
 
-        for k in 0..2 {
-            let (modifier, color) = if k == 0 {
-                (0, color::Color::WHITE)
-            } else {
-                (5, color::Color::BLACK)
-            };
-            for (i, file) in board.iter_mut().enumerate().take(8) {
-                file[1 + modifier] = Some(Box::new(pawn::Pawn {
-                    color,
-                    position: position::Position(i as u8 + 1, 2 + modifier as u8),
-                }));
+        let back_rank = [
+            piece::PieceEnum::ROOK,
+            piece::PieceEnum::KNIGHT,
+            piece::PieceEnum::BISHOP,
+            piece::PieceEnum::QUEEN,
+            piece::PieceEnum::KING,
+            piece::PieceEnum::BISHOP,
+            piece::PieceEnum::KNIGHT,
+            piece::PieceEnum::ROOK,
+        ];
+
+        for (color, first_rank, pawn_rank) in
+            &[(color::Color::WHITE, 1, 2), (color::Color::BLACK, 8, 7)]
+        {
+            for (i, piece) in back_rank.iter().enumerate() {
+                let file = i as u8 + 1;
+                board.set_piece(
+                    position::Position(file, *first_rank),
+                    Some(piece::ColoredPiece {
+                        piece: *piece,
+                        color: *color,
+                    }),
+                );
+                board.set_piece(
+                    position::Position(file, *pawn_rank),
+                    Some(piece::ColoredPiece {
+                        piece: piece::PieceEnum::PAWN,
+                        color: *color,
+                    }),
+                );
             }
         }
 
-        for k in 0..2 {
-            let (modifier, color) = if k == 0 {
-                (0, color::Color::WHITE)
-            } else {
-                (7, color::Color::BLACK)
-            };
-
-            let rook_positions = [(1, 1), (8, 1)];
-
-            for rook in &rook_positions {
-                board[rook.0 - 1][rook.1 - 1 + modifier] = Some(Box::new(rook::Rook {
-                    color,
-                    position: position::Position(rook.0 as u8, rook.1 as u8 + modifier as u8),
-                }));
-            }
+        board
+    }
 
-            let knight_positions = [(2, 1), (7, 1)];
+    pub fn piece_at(&self, position: position::Position) -> Option<piece::ColoredPiece> {
+        self.squares[index(position)]
+    }
 
-            for knight in &knight_positions {
-                board[knight.0 - 1][knight.1 - 1 + modifier] = Some(Box::new(knight::Knight {
-                    color,
-                    position: position::Position(knight.0 as u8, knight.1 as u8 + modifier as u8),
-                }));
-            }
+    pub fn piece_on(&self, square: square::Square) -> Option<piece::ColoredPiece> {
+        self.squares[square.index() as usize]
+    }
 
-            let bishop_positions = [(3, 1), (6, 1)];
+    pub fn set_piece(&mut self, position: position::Position, piece: Option<piece::ColoredPiece>) {
+        self.squares[index(position)] = piece;
+    }
 
-            for bishop in &bishop_positions {
-                board[bishop.0 - 1][bishop.1 - 1 + modifier] = Some(Box::new(bishop::Bishop {
-                    color,
-                    position: position::Position(bishop.0 as u8, bishop.1 as u8 + modifier as u8),
-                }));
+    // files from a to h, each with its squares from rank 1 to 8
+    pub fn iter(&self) -> impl Iterator<Item = [Option<piece::PlacedPiece>; 8]> + '_ {
+        (1..=8).map(move |file| {
+            let mut squares = [None; 8];
+            for (i, square) in squares.iter_mut().enumerate() {
+                *square = self.get_square(position::Position(file, i as u8 + 1));
             }
-
-            board[3][modifier] = Some(Box::new(queen::Queen {
-                color,
-                position: position::Position(4, 1 + modifier as u8),
-            }));
-
-            board[4][modifier] = Some(Box::new(king::King {
-                color,
-                position: position::Position(5, 1 + modifier as u8),
-            }));
-        }
-
-        Self { board }
+            squares
+        })
     }
 
-    pub fn iter(&self) -> slice::Iter<Vec<Option<Box<dyn piece::Piece>>>> {
-        self.board.iter()
+    pub fn get_square(&self, position: position::Position) -> Option<piece::PlacedPiece> {
+        self.piece_at(position)
+            .map(|piece| piece.placed_at(position))
     }
 
-    pub fn get_square(&self, position: position::Position) -> &Option<Box<dyn piece::Piece>> {
-        &self.board[position.0 as usize - 1][position.1 as usize - 1]
+    pub fn take_piece(&mut self, position: position::Position) -> piece::ColoredPiece {
+        match self.try_take_piece(position) {
+            Err(_) => panic!("No piece at position: {:?}", position),
+            Ok(piece) => piece,
+        }
     }
 
-    pub fn take_piece(&mut self, position: position::Position) -> Box<dyn piece::Piece> {
-        let piece = self.board[position.0 as usize - 1][position.1 as usize - 1].take();
-
-        match piece {
-            None => panic!("No piece at position: {:?}", position),
-            Some(piece) => piece,
-        }
+    pub fn try_take_piece(
+        &mut self,
+        position: position::Position,
+    ) -> Result<piece::ColoredPiece, error::Error> {
+        self.squares[index(position)]
+            .take()
+            .ok_or(error::Error::EmptySquare(position))
     }
 
     pub fn is_empty(&self, pos: position::Position) -> bool {
-        self.get_square(pos).is_none()
+        self.piece_at(pos).is_none()
     }
 
+    // only the kind and colour of the given piece are kept, it is placed on `position`
     pub fn set_square(
         &mut self,
         square: Option<Box<dyn piece::Piece>>,
         position: position::Position,
     ) {
-        self.board[position.0 as usize - 1][position.1 as usize - 1] = square;
+        let piece = square.map(|piece| piece::ColoredPiece {
+            piece: piece.piece(),
+            color: *piece.color(),
+        });
+        self.set_piece(position, piece);
     }
 
     pub fn to_u8_board(&self) -> [u8; 64] {
         let mut u8_board = [0; 64];
 
-        let mut index = 0;
-
-        for file in &self.board {
-            for square in file {
-                match square {
-                    None => u8_board[index] = 0,
-                    Some(piece) => match (*piece.color(), piece.piece()) {
-                        (color::Color::WHITE, piece::PieceEnum::PAWN) => u8_board[index] = 1,
-                        (color::Color::BLACK, piece::PieceEnum::PAWN) => u8_board[index] = 11,
-                        (color::Color::WHITE, piece::PieceEnum::ROOK) => u8_board[index] = 2,
-                        (color::Color::BLACK, piece::PieceEnum::ROOK) => u8_board[index] = 12,
-                        (color::Color::WHITE, piece::PieceEnum::KNIGHT) => u8_board[index] = 3,
-                        (color::Color::BLACK, piece::PieceEnum::KNIGHT) => u8_board[index] = 13,
-                        (color::Color::WHITE, piece::PieceEnum::BISHOP) => u8_board[index] = 4,
-                        (color::Color::BLACK, piece::PieceEnum::BISHOP) => u8_board[index] = 14,
-                        (color::Color::WHITE, piece::PieceEnum::QUEEN) => u8_board[index] = 5,
-                        (color::Color::BLACK, piece::PieceEnum::QUEEN) => u8_board[index] = 15,
-                        (color::Color::WHITE, piece::PieceEnum::KING) => u8_board[index] = 6,
-                        (color::Color::BLACK, piece::PieceEnum::KING) => u8_board[index] = 16,
-                    },
-                };
-                index += 1;
-            }
+        for (i, code) in u8_board.iter_mut().enumerate() {
+            let position = position::Position(i as u8 / 8 + 1, i as u8 % 8 + 1);
+            *code = match self.piece_at(position) {
+                None => 0,
+                Some(piece) => piece_to_code(piece),
+            };
         }
         u8_board
     }
 
     pub fn from_u8_board(board_arr: &[u8; 64]) -> (Self, position::Position, position::Position) {
+        match Self::try_from_u8_board(board_arr) {
+            Ok(board) => board,
+            Err(error::Error::MissingKing(color::Color::WHITE)) => {
+                panic!("No white king in given board string")
+            }
+            Err(error::Error::MissingKing(color::Color::BLACK)) => {
+                panic!("No black king in given board string")
+            }
+            Err(_) => panic!("Invalid piece number"),
+        }
+    }
+
+    pub fn try_from_u8_board(
+        board_arr: &[u8; 64],
+    ) -> Result<(Self, position::Position, position::Position), error::Error> {
         let mut board = Self::empty();
-        let mut file = 1;
-        let mut rank = 1;
-        let mut white_king = position::Position(0, 0);
-        let mut black_king = position::Position(0, 0);
-
-        for string_piece in board_arr.iter().take(64) {
-            let square = string_piece.to_piece(position::Position(file, rank));
-            if let Some(p) = &square {
-                match (p.color(), p.piece()) {
-                    (color::Color::WHITE, piece::PieceEnum::KING) => {
-                        white_king = position::Position(file, rank)
-                    }
-                    (color::Color::BLACK, piece::PieceEnum::KING) => {
-                        black_king = position::Position(file, rank)
-                    }
+        let mut white_king = None;
+        let mut black_king = None;
+
+        for (i, code) in board_arr.iter().enumerate() {
+            let position = position::Position(i as u8 / 8 + 1, i as u8 % 8 + 1);
+            let square = code_to_piece(*code)?;
+            if let Some(p) = square {
+                match (p.color, p.piece) {
+                    (color::Color::WHITE, piece::PieceEnum::KING) => white_king = Some(position),
+                    (color::Color::BLACK, piece::PieceEnum::KING) => black_king = Some(position),
                     (_, _) => (),
                 }
             }
-            board.set_square(square, position::Position(file, rank));
-            if rank == 8 {
-                rank = 1;
-                file += 1;
-            } else {
-                rank += 1;
-            }
+            board.set_piece(position, square);
         }
 
         match (white_king, black_king) {
-            (position::Position(0, 0), _) => panic!("No white king in given board string"),
-            (_, position::Position(0, 0)) => panic!("No black king in given board string"),
-            (_, _) => (),
-        };
-
-        (board, white_king, black_king)
+            (None, _) => Err(error::Error::MissingKing(color::Color::WHITE)),
+            (_, None) => Err(error::Error::MissingKing(color::Color::BLACK)),
+            (Some(white_king), Some(black_king)) => Ok((board, white_king, black_king)),
+        }
     }
 
-    pub fn pieces_of_color_except_king(&self, color: color::Color) -> Vec<&Box<dyn piece::Piece>> {
+    pub fn pieces_of_color_except_king(&self, color: color::Color) -> Vec<piece::PlacedPiece> {
         let mut pieces = vec![];
-        for file in self.board.iter() {
-            for square in file.iter() {
-                if let Some(piece) = square {
-                    if *piece.color() == color && piece.piece() != piece::PieceEnum::KING {
+        for file in 1..=8 {
+            for rank in 1..=8 {
+                if let Some(piece) = self.get_square(position::Position(file, rank)) {
+                    if piece.color == color && piece.piece != piece::PieceEnum::KING {
                         pieces.push(piece);
                     }
                 }
@@ -206,11 +195,131 @@ impl Board {
     }
 }
 
+fn piece_to_code(piece: piece::ColoredPiece) -> u8 {
+    let code = match piece.piece {
+        piece::PieceEnum::PAWN => 1,
+        piece::PieceEnum::ROOK => 2,
+        piece::PieceEnum::KNIGHT => 3,
+        piece::PieceEnum::BISHOP => 4,
+        piece::PieceEnum::QUEEN => 5,
+        piece::PieceEnum::KING => 6,
+    };
+    match piece.color {
+        color::Color::WHITE => code,
+        color::Color::BLACK => code + 10,
+    }
+}
+
+fn code_to_piece(code: u8) -> Result<Option<piece::ColoredPiece>, error::Error> {
+    let color = match code {
+        0 => return Ok(None),
+        1..=6 => color::Color::WHITE,
+        11..=16 => color::Color::BLACK,
+        _ => return Err(error::Error::InvalidPieceCode(code)),
+    };
+    let piece = match code % 10 {
+        1 => piece::PieceEnum::PAWN,
+        2 => piece::PieceEnum::ROOK,
+        3 => piece::PieceEnum::KNIGHT,
+        4 => piece::PieceEnum::BISHOP,
+        5 => piece::PieceEnum::QUEEN,
+        _ => piece::PieceEnum::KING,
+    };
+    Ok(Some(piece::ColoredPiece { piece, color }))
+}
+
 #[cfg(test)]
 mod tests {
+    use super::super::pieces::{knight, piece::Piece};
     use super::*;
 
     #[test]
+    fn board_is_copy() {
+        let board = Board::initial();
+        let mut copy = board;
+        copy.set_piece(position::Position(5, 2), None);
+
+        assert!(board.piece_at(position::Position(5, 2)).is_some());
+        assert!(copy.piece_at(position::Position(5, 2)).is_none());
+        assert_ne!(board, copy);
+    }
+
+    #[test]
+    fn piece_on_square() {
+        let board = Board::initial();
+        for square in square::Square::all() {
+            assert_eq!(board.piece_at(square.into()), board.piece_on(square));
+        }
+        assert_eq!(
+            Some(piece::ColoredPiece {
+                piece: piece::PieceEnum::QUEEN,
+                color: color::Color::BLACK
+            }),
+            board.piece_on("d8".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn set_square_places_piece_on_given_square() {
+        let mut board = Board::empty();
+        let knight = knight::Knight {
+            position: position::Position(1, 1),
+            color: color::Color::BLACK,
+        };
+        board.set_square(Some(Box::new(knight)), position::Position(3, 6));
+
+        assert!(board.is_empty(position::Position(1, 1)));
+        match board.get_square(position::Position(3, 6)) {
+            Some(piece) => {
+                assert_eq!(position::Position(3, 6), *piece.position());
+                assert_eq!(piece::PieceEnum::KNIGHT, piece.piece());
+                assert_eq!(color::Color::BLACK, *piece.color());
+            }
+            None => panic!("Should've been a knight"),
+        }
+    }
+
+    #[test]
+    fn u8_board_round_trip() {
+        let u8_board = Board::initial().to_u8_board();
+        assert_eq!([2, 1, 0, 0, 0, 0, 11, 12], u8_board[..8]);
+        let (board, white_king, black_king) = Board::from_u8_board(&u8_board);
+        assert_eq!(Board::initial(), board);
+        assert_eq!(position::Position(5, 1), white_king);
+        assert_eq!(position::Position(5, 8), black_king);
+    }
+
+    #[test]
+    fn try_take_piece() {
+        let mut board = Board::initial();
+        assert!(board.try_take_piece(position::Position(5, 2)).is_ok());
+        assert_eq!(
+            error::Error::EmptySquare(position::Position(5, 2)),
+            board.try_take_piece(position::Position(5, 2)).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn try_from_u8_board() {
+        let mut u8_board = Board::initial().to_u8_board();
+        assert!(Board::try_from_u8_board(&u8_board).is_ok());
+
+        u8_board[1] = 7;
+        assert_eq!(
+            error::Error::InvalidPieceCode(7),
+            Board::try_from_u8_board(&u8_board).unwrap_err()
+        );
+
+        u8_board[1] = 1;
+        u8_board[39] = 0;
+        assert_eq!(
+            error::Error::MissingKing(color::Color::BLACK),
+            Board::try_from_u8_board(&u8_board).unwrap_err()
+        );
+    }
+
+    #[test]
+    #[allow(clippy::redundant_pattern_matching)]
     fn initial() {
         let initial_board = Board::initial();
         for i in 0..8 {
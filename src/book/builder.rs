@@ -0,0 +1,200 @@
+use crate::pgn::{GameResult, PgnGame};
+use crate::{chessmove, color, fen, game};
+use std::collections::HashMap;
+use std::io;
+
+const DEFAULT_MAX_PLY: usize = 20;
+
+#[derive(Default, Clone, Copy)]
+struct MoveStats {
+    occurrences: u32,
+    // two points per win and one per draw of the side that played the move
+    score: u64,
+}
+
+// Collects the moves of many games into a Polyglot book. Every move up to the maximum ply is
+// counted and weighted by how the games ended for the side that played it.
+pub struct BookBuilder {
+    max_ply: usize,
+    min_occurrences: u32,
+    stats: HashMap<(u64, u16), MoveStats>,
+}
+
+impl Default for BookBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BookBuilder {
+    pub fn new() -> Self {
+        Self {
+            max_ply: DEFAULT_MAX_PLY,
+            min_occurrences: 1,
+            stats: HashMap::new(),
+        }
+    }
+
+    pub fn max_ply(mut self, max_ply: usize) -> Self {
+        self.max_ply = max_ply;
+        self
+    }
+
+    // moves played in fewer games are left out of the book
+    pub fn min_occurrences(mut self, min_occurrences: u32) -> Self {
+        self.min_occurrences = min_occurrences;
+        self
+    }
+
+    pub fn add_pgn_game(&mut self, pgn_game: &PgnGame) -> Result<(), fen::FenError> {
+        let game = pgn_game.starting_position()?;
+        let result = pgn_game.result.unwrap_or(GameResult::Undecided);
+        self.add_game(game, &pgn_game.mainline(), result);
+        Ok(())
+    }
+
+    // panics if one of the moves is not legal
+    pub fn add_game(
+        &mut self,
+        mut game: game::Game,
+        moves: &[chessmove::ChessMove],
+        result: GameResult,
+    ) {
+        for mv in moves.iter().take(self.max_ply) {
+            let score = match (result, game.side_to_move()) {
+                (GameResult::WhiteWins, color::Color::WHITE) => 2,
+                (GameResult::BlackWins, color::Color::BLACK) => 2,
+                (GameResult::Draw, _) => 1,
+                _ => 0,
+            };
+            let stats = self
+                .stats
+                .entry((super::polyglot_key(&game), super::encode_move(&game, mv)))
+                .or_default();
+            stats.occurrences += 1;
+            stats.score += score;
+            game.make_move(*mv);
+        }
+    }
+
+    // the book as Polyglot entries, sorted by key and then by descending weight
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries = self
+            .stats
+            .iter()
+            .filter(|(_, stats)| stats.occurrences >= self.min_occurrences)
+            .map(|(&(key, raw_move), stats)| (key, raw_move, stats.score))
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)).then(a.1.cmp(&b.1)));
+
+        // weights only have 16 bits, so positions with large scores are scaled down together
+        let mut max_scores = HashMap::new();
+        for (key, _, score) in &entries {
+            let max_score = max_scores.entry(*key).or_insert(0);
+            *max_score = u64::max(*max_score, *score);
+        }
+
+        let mut bytes = Vec::with_capacity(entries.len() * 16);
+        for (key, raw_move, score) in entries {
+            bytes.extend_from_slice(&key.to_be_bytes());
+            bytes.extend_from_slice(&raw_move.to_be_bytes());
+            bytes.extend_from_slice(&weight(score, max_scores[&key]).to_be_bytes());
+            bytes.extend_from_slice(&0u32.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+// a move that scored anything keeps a weight of at least one
+fn weight(score: u64, max_score: u64) -> u16 {
+    if max_score <= u16::MAX as u64 {
+        return score as u16;
+    }
+    u64::max(score * u16::MAX as u64 / max_score, (score > 0) as u64) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::reader::PolyglotBook;
+    use super::*;
+    use crate::pgn::reader::PgnReader;
+
+    const GAMES: &str = r#"[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. O-O 1-0
+
+[Result "0-1"]
+
+1. e4 c5 2. Nf3 d6 0-1
+
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nf6 1/2-1/2
+
+[Result "*"]
+
+1. d4 d5 *
+"#;
+
+    fn build(mut builder: BookBuilder) -> PolyglotBook {
+        for pgn_game in PgnReader::new(GAMES.as_bytes()) {
+            builder.add_pgn_game(&pgn_game.unwrap()).unwrap();
+        }
+        let mut bytes = vec![];
+        builder.write(&mut bytes).unwrap();
+        PolyglotBook::from_bytes(bytes).unwrap()
+    }
+
+    fn moves(book: &PolyglotBook, game: &game::Game) -> Vec<(String, u16)> {
+        book.entries(game)
+            .iter()
+            .map(|entry| (entry.chessmove.to_string(), entry.weight))
+            .collect()
+    }
+
+    #[test]
+    fn weights_follow_results() {
+        let book = build(BookBuilder::new());
+        let mut game = game::Game::new();
+        assert_eq!(
+            vec![("e2e4".to_string(), 3), ("d2d4".to_string(), 0)],
+            moves(&book, &game)
+        );
+
+        game::play(&mut game, &["e4"]);
+        assert_eq!(
+            vec![("c7c5".to_string(), 2), ("e7e5".to_string(), 1)],
+            moves(&book, &game)
+        );
+
+        game::play(&mut game, &["e5", "Nf3", "Nc6", "Bc4", "Nf6"]);
+        assert_eq!(vec![("e1g1".to_string(), 2)], moves(&book, &game));
+    }
+
+    #[test]
+    fn max_ply_and_min_occurrences() {
+        let book = build(BookBuilder::new().max_ply(2));
+        let mut game = game::Game::new();
+        game::play(&mut game, &["e4", "e5"]);
+        assert!(book.entries(&game).is_empty());
+
+        let book = build(BookBuilder::new().min_occurrences(2));
+        let mut game = game::Game::new();
+        assert_eq!(vec![("e2e4".to_string(), 3)], moves(&book, &game));
+        game::play(&mut game, &["e4", "e5"]);
+        assert_eq!(vec![("g1f3".to_string(), 3)], moves(&book, &game));
+    }
+
+    #[test]
+    fn large_scores_are_scaled_down() {
+        assert_eq!(3, weight(3, 5));
+        assert_eq!(65535, weight(80_000, 80_000));
+        assert_eq!(32767, weight(40_000, 80_000));
+        assert_eq!(1, weight(1, 1_000_000));
+        assert_eq!(0, weight(0, 1_000_000));
+    }
+}
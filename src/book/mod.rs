@@ -0,0 +1,200 @@
+pub mod builder;
+pub mod reader;
+
+mod random64;
+
+use crate::pieces::{piece, position};
+use crate::{chessmove, color, game};
+use random64::RANDOM64;
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+// a move as stored in a book, weights are relative to the other moves of the position
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BookEntry {
+    pub chessmove: chessmove::ChessMove,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+// the key Polyglot books are indexed by
+pub fn polyglot_key(game: &game::Game) -> u64 {
+    let mut key = 0;
+
+    for file in 1..=8 {
+        for rank in 1..=8 {
+            if let Some(piece) = game.board().piece_at(position::Position(file, rank)) {
+                let kind = match piece.piece {
+                    piece::PieceEnum::PAWN => 0,
+                    piece::PieceEnum::KNIGHT => 2,
+                    piece::PieceEnum::BISHOP => 4,
+                    piece::PieceEnum::ROOK => 6,
+                    piece::PieceEnum::QUEEN => 8,
+                    piece::PieceEnum::KING => 10,
+                } + match piece.color {
+                    color::Color::BLACK => 0,
+                    color::Color::WHITE => 1,
+                };
+                key ^= RANDOM64[64 * kind + 8 * (rank as usize - 1) + file as usize - 1];
+            }
+        }
+    }
+
+    let (white_kingside, white_queenside) = game.castling_rights_white();
+    let (black_kingside, black_queenside) = game.castling_rights_black();
+    for (i, right) in [
+        white_kingside,
+        white_queenside,
+        black_kingside,
+        black_queenside,
+    ]
+    .iter()
+    .enumerate()
+    {
+        if *right {
+            key ^= RANDOM64[CASTLING_OFFSET + i];
+        }
+    }
+
+    // Polyglot only hashes the en passant file if a pawn could capture, legal or not
+    if let Some(ep) = game.en_passant() {
+        let (pawn_rank, pawn) = match game.side_to_move() {
+            color::Color::WHITE => (5, color::Color::WHITE),
+            color::Color::BLACK => (4, color::Color::BLACK),
+        };
+        let can_capture = [ep.0 as i8 - 1, ep.0 as i8 + 1]
+            .iter()
+            .filter(|file| (1..=8).contains(*file))
+            .any(|file| {
+                game.board()
+                    .piece_at(position::Position(*file as u8, pawn_rank))
+                    == Some(piece::ColoredPiece {
+                        piece: piece::PieceEnum::PAWN,
+                        color: pawn,
+                    })
+            });
+        if can_capture {
+            key ^= RANDOM64[EN_PASSANT_OFFSET + ep.0 as usize - 1];
+        }
+    }
+
+    if *game.side_to_move() == color::Color::WHITE {
+        key ^= RANDOM64[TURN_OFFSET];
+    }
+
+    key
+}
+
+// Polyglot stores castling as the king capturing its own rook
+pub(crate) fn decode_move(game: &game::Game, raw: u16) -> chessmove::ChessMove {
+    let square = |bits: u16| ((bits & 7) as u8 + 1, (bits >> 3 & 7) as u8 + 1);
+    let from = square(raw >> 6);
+    let mut to = square(raw);
+    let promotion = match raw >> 12 & 7 {
+        1 => Some(piece::PromotionPiece::Knight),
+        2 => Some(piece::PromotionPiece::Bishop),
+        3 => Some(piece::PromotionPiece::Rook),
+        4 => Some(piece::PromotionPiece::Queen),
+        _ => None,
+    };
+
+    if is_king(game, from) && from.0 == 5 && from.1 == to.1 {
+        match to.0 {
+            8 => to.0 = 7,
+            1 => to.0 = 3,
+            _ => (),
+        }
+    }
+
+    chessmove::ChessMove {
+        from,
+        to,
+        promotion,
+    }
+}
+
+pub(crate) fn encode_move(game: &game::Game, mv: &chessmove::ChessMove) -> u16 {
+    let mut to = mv.to;
+    if is_king(game, mv.from) && (mv.from).0 == 5 {
+        match (mv.to).0 {
+            7 => to.0 = 8,
+            3 => to.0 = 1,
+            _ => (),
+        }
+    }
+
+    let square = |(file, rank): (u8, u8)| (rank as u16 - 1) << 3 | (file as u16 - 1);
+    let promotion = match mv.promotion {
+        None => 0,
+        Some(piece::PromotionPiece::Knight) => 1,
+        Some(piece::PromotionPiece::Bishop) => 2,
+        Some(piece::PromotionPiece::Rook) => 3,
+        Some(piece::PromotionPiece::Queen) => 4,
+    };
+    promotion << 12 | square(mv.from) << 6 | square(to)
+}
+
+fn is_king(game: &game::Game, (file, rank): (u8, u8)) -> bool {
+    match game.board().piece_at(position::Position(file, rank)) {
+        Some(piece) => piece.piece == piece::PieceEnum::KING,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the examples from the Polyglot book format specification
+    #[test]
+    fn polyglot_keys() {
+        let mut game = game::Game::new();
+        assert_eq!(0x463b96181691fc9c, polyglot_key(&game));
+
+        let expected = [
+            ("e4", 0x823c9b50fd114196),
+            ("d5", 0x0756b94461c50fb0),
+            ("e5", 0x662fafb965db29d4),
+            ("f5", 0x22a48b5a8e47ff78),
+            ("Ke2", 0x652a607ca3f242c1),
+            ("Kf7", 0x00fdd303c946bdd9),
+        ];
+        for (san, key) in expected.iter() {
+            game::play(&mut game, &[san]);
+            assert_eq!(*key, polyglot_key(&game), "{}", san);
+        }
+
+        let mut game = game::Game::new();
+        game::play(&mut game, &["a4", "b5", "h4", "b4", "c4"]);
+        assert_eq!(0x3c8123ea7b067637, polyglot_key(&game));
+        game::play(&mut game, &["bxc3", "Ra3"]);
+        assert_eq!(0x5c3f9b829b279560, polyglot_key(&game));
+    }
+
+    #[test]
+    fn castling_uses_king_takes_rook() {
+        let game =
+            game::Game::from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+        for (uci, raw) in &[("e1g1", 0x0107), ("e1c1", 0x0100), ("a2a4", 0x0218)] {
+            let mv = game.parse_uci_move(uci).unwrap();
+            assert_eq!(mv, decode_move(&game, *raw), "{}", uci);
+            assert_eq!(*raw, encode_move(&game, &mv), "{}", uci);
+        }
+
+        // a rook move from e1 to h1 is not castling
+        let game = game::Game::from_fen("7k/8/8/8/8/8/6K1/4R3 w - - 0 1").unwrap();
+        let mv = game.parse_uci_move("e1h1").unwrap();
+        assert_eq!(mv, decode_move(&game, 0x0107));
+        assert_eq!(0x0107, encode_move(&game, &mv));
+    }
+
+    #[test]
+    fn promotion() {
+        let game = game::Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mv = game.parse_uci_move("b7b8n").unwrap();
+        assert_eq!(mv, decode_move(&game, 0x1000 | 0x31 << 6 | 0x39));
+        assert_eq!(0x1000 | 0x31 << 6 | 0x39, encode_move(&game, &mv));
+    }
+}
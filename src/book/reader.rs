@@ -0,0 +1,120 @@
+use super::BookEntry;
+use crate::game;
+use std::io::Read;
+use std::{error, fmt, fs, path};
+
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum BookError {
+    Io(String),
+    // Polyglot entries are 16 bytes each
+    InvalidLength(usize),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::Io(e) => write!(f, "{}", e),
+            BookError::InvalidLength(length) => {
+                write!(f, "{} bytes is not a whole number of book entries", length)
+            }
+        }
+    }
+}
+
+impl error::Error for BookError {}
+
+// A Polyglot opening book read into memory. Entries are sorted by key, which makes lookups a
+// binary search.
+pub struct PolyglotBook {
+    bytes: Vec<u8>,
+}
+
+impl PolyglotBook {
+    // `usize::is_multiple_of` needs Rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, BookError> {
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err(BookError::InvalidLength(bytes.len()));
+        }
+        Ok(Self { bytes })
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, BookError> {
+        let mut bytes = vec![];
+        if let Err(e) = reader.read_to_end(&mut bytes) {
+            return Err(BookError::Io(e.to_string()));
+        }
+        Self::from_bytes(bytes)
+    }
+
+    pub fn open<P: AsRef<path::Path>>(path: P) -> Result<Self, BookError> {
+        match fs::File::open(path) {
+            Ok(file) => Self::read(file),
+            Err(e) => Err(BookError::Io(e.to_string())),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len() / ENTRY_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    // the book moves for the position, entries for moves that are not legal are skipped
+    pub fn entries(&self, game: &game::Game) -> Vec<BookEntry> {
+        let key = super::polyglot_key(game);
+        let legal_moves = game.legal_moves();
+
+        let mut low = 0;
+        let mut high = self.len();
+        while low < high {
+            let middle = (low + high) / 2;
+            if self.key(middle) < key {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        (low..self.len())
+            .take_while(|i| self.key(*i) == key)
+            .map(|i| {
+                let entry = &self.bytes[i * ENTRY_SIZE..(i + 1) * ENTRY_SIZE];
+                BookEntry {
+                    chessmove: super::decode_move(game, u16::from_be_bytes([entry[8], entry[9]])),
+                    weight: u16::from_be_bytes([entry[10], entry[11]]),
+                    learn: u32::from_be_bytes([entry[12], entry[13], entry[14], entry[15]]),
+                }
+            })
+            .filter(|entry| legal_moves.contains(&entry.chessmove))
+            .collect()
+    }
+
+    // `random` picks the move, each with a chance proportional to its weight
+    pub fn weighted_pick(&self, game: &game::Game, random: u64) -> Option<BookEntry> {
+        let entries = self.entries(game);
+        let total = entries.iter().map(|entry| entry.weight as u64).sum::<u64>();
+        if total == 0 {
+            return None;
+        }
+
+        let mut choice = random % total;
+        for entry in entries {
+            if choice < entry.weight as u64 {
+                return Some(entry);
+            }
+            choice -= entry.weight as u64;
+        }
+        None
+    }
+
+    fn key(&self, index: usize) -> u64 {
+        let mut key = [0; 8];
+        key.copy_from_slice(&self.bytes[index * ENTRY_SIZE..index * ENTRY_SIZE + 8]);
+        u64::from_be_bytes(key)
+    }
+}
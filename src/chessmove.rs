@@ -1,4 +1,6 @@
-use crate::pieces::piece;
+use crate::pieces::{piece, position};
+use crate::{fen, square};
+use std::{error, fmt, ops, slice, str};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct ChessMove {
@@ -6,3 +8,342 @@ pub struct ChessMove {
     pub to: (u8, u8),
     pub promotion: Option<piece::PromotionPiece>,
 }
+
+impl ChessMove {
+    pub fn new(
+        from: square::Square,
+        to: square::Square,
+        promotion: Option<piece::PromotionPiece>,
+    ) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            promotion,
+        }
+    }
+}
+
+// no legal position has more moves than this
+pub const MAX_MOVES: usize = 256;
+
+// A fixed-capacity list of moves that lives on the stack, so generating moves into it does not
+// allocate. It dereferences to a slice of the moves pushed so far.
+#[derive(Clone, Copy)]
+pub struct MoveList {
+    moves: [ChessMove; MAX_MOVES],
+    len: usize,
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        Self {
+            moves: [ChessMove {
+                from: (0, 0),
+                to: (0, 0),
+                promotion: None,
+            }; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    // panics once the list is full
+    pub fn push(&mut self, mv: ChessMove) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    // keeps the moves from `start` onwards only if `keep` holds for them
+    pub(crate) fn retain_from(&mut self, start: usize, mut keep: impl FnMut(&ChessMove) -> bool) {
+        let mut len = start;
+        for i in start..self.len {
+            if keep(&self.moves[i]) {
+                self.moves[len] = self.moves[i];
+                len += 1;
+            }
+        }
+        self.len = len;
+    }
+}
+
+impl Extend<ChessMove> for MoveList {
+    fn extend<I: IntoIterator<Item = ChessMove>>(&mut self, moves: I) {
+        for mv in moves {
+            self.push(mv);
+        }
+    }
+}
+
+impl ops::Deref for MoveList {
+    type Target = [ChessMove];
+
+    fn deref(&self) -> &[ChessMove] {
+        &self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a ChessMove;
+    type IntoIter = slice::Iter<'a, ChessMove>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MoveKind {
+    // any other move, capturing or not
+    Normal,
+    DoublePawnPush,
+    EnPassant,
+    CastleKingside,
+    CastleQueenside,
+    Promotion,
+}
+
+// a legal move together with what it does on the board it was generated for
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MoveInfo {
+    pub chessmove: ChessMove,
+    pub piece: piece::PieceEnum,
+    // the square differs from the move's target for en passant
+    pub captured: Option<(piece::PieceEnum, square::Square)>,
+    pub kind: MoveKind,
+}
+
+impl MoveInfo {
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+
+    pub fn is_castle(&self) -> bool {
+        self.kind == MoveKind::CastleKingside || self.kind == MoveKind::CastleQueenside
+    }
+}
+
+// Where the move generators put the moves they find. With every move they pass what they know
+// about it, which only a list of `MoveInfo` asks for.
+pub(crate) trait MoveSink {
+    fn add(&mut self, mv: ChessMove, info: impl FnOnce() -> MoveInfo);
+}
+
+impl MoveSink for MoveList {
+    fn add(&mut self, mv: ChessMove, _info: impl FnOnce() -> MoveInfo) {
+        self.push(mv);
+    }
+}
+
+impl MoveSink for Vec<MoveInfo> {
+    fn add(&mut self, _mv: ChessMove, info: impl FnOnce() -> MoveInfo) {
+        self.push(info());
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum UciMoveError {
+    Malformed(String),
+    IllegalMove(ChessMove),
+}
+
+impl fmt::Display for UciMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UciMoveError::Malformed(s) => write!(f, "'{}' is not a UCI move", s),
+            UciMoveError::IllegalMove(mv) => write!(f, "{} is not a legal move", mv),
+        }
+    }
+}
+
+impl error::Error for UciMoveError {}
+
+// UCI long algebraic notation, e.g. "e2e4" or "e7e8q". Castling is written as a king move.
+impl fmt::Display for ChessMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            fen::square_name(position::Position(self.from.0, self.from.1)),
+            fen::square_name(position::Position(self.to.0, self.to.1))
+        )?;
+        match self.promotion {
+            None => Ok(()),
+            Some(piece::PromotionPiece::Rook) => write!(f, "r"),
+            Some(piece::PromotionPiece::Knight) => write!(f, "n"),
+            Some(piece::PromotionPiece::Bishop) => write!(f, "b"),
+            Some(piece::PromotionPiece::Queen) => write!(f, "q"),
+        }
+    }
+}
+
+impl str::FromStr for ChessMove {
+    type Err = UciMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || UciMoveError::Malformed(s.to_string());
+
+        if !s.is_ascii() || (s.len() != 4 && s.len() != 5) {
+            return Err(malformed());
+        }
+
+        let from = fen::parse_square(&s[0..2]).ok_or_else(malformed)?;
+        let to = fen::parse_square(&s[2..4]).ok_or_else(malformed)?;
+        let promotion = match &s[4..] {
+            "" => None,
+            "r" => Some(piece::PromotionPiece::Rook),
+            "n" => Some(piece::PromotionPiece::Knight),
+            "b" => Some(piece::PromotionPiece::Bishop),
+            "q" => Some(piece::PromotionPiece::Queen),
+            _ => return Err(malformed()),
+        };
+
+        Ok(ChessMove {
+            from: (from.0, from.1),
+            to: (to.0, to.1),
+            promotion,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game;
+
+    #[test]
+    fn display() {
+        let mv = ChessMove {
+            from: (5, 2),
+            to: (5, 4),
+            promotion: None,
+        };
+        assert_eq!("e2e4", mv.to_string());
+
+        let mv = ChessMove {
+            from: (5, 7),
+            to: (5, 8),
+            promotion: Some(piece::PromotionPiece::Queen),
+        };
+        assert_eq!("e7e8q", mv.to_string());
+        assert_eq!(
+            mv,
+            ChessMove::new(
+                "e7".parse().unwrap(),
+                "e8".parse().unwrap(),
+                Some(piece::PromotionPiece::Queen)
+            )
+        );
+
+        let mv = ChessMove {
+            from: (0, 0),
+            to: (9, 9),
+            promotion: None,
+        };
+        assert_eq!("(0, 0)(9, 9)", mv.to_string());
+        assert_eq!(
+            "(0, 0)(9, 9) is not a legal move",
+            game::Game::new().try_make_move(mv).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn move_list() {
+        let mut moves = MoveList::new();
+        assert!(moves.is_empty());
+
+        let game = game::Game::new();
+        for mv in game.legal_moves() {
+            moves.push(mv);
+        }
+        assert_eq!(20, moves.len());
+        assert_eq!(game.legal_moves(), moves.to_vec());
+
+        moves.retain_from(16, |mv| mv.to.1 == 3);
+        assert_eq!(18, moves.len());
+        assert_eq!(game.legal_moves()[..16], moves[..16]);
+        assert!(moves[16..].iter().all(|mv| mv.to.1 == 3));
+
+        moves.clear();
+        assert!(moves.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn move_list_is_bounded() {
+        let mut moves = MoveList::new();
+        let mv = "e2e4".parse::<ChessMove>().unwrap();
+        for _ in 0..=MAX_MOVES {
+            moves.push(mv);
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            Ok(ChessMove {
+                from: (5, 1),
+                to: (7, 1),
+                promotion: None,
+            }),
+            "e1g1".parse::<ChessMove>()
+        );
+        assert_eq!(
+            Ok(ChessMove {
+                from: (2, 2),
+                to: (1, 1),
+                promotion: Some(piece::PromotionPiece::Knight),
+            }),
+            "b2a1n".parse::<ChessMove>()
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        for s in &["", "e2", "e2e9", "i2e4", "e2e4k", "e2e4qq", "e2-e4", "é2e4"] {
+            assert_eq!(
+                Err(UciMoveError::Malformed(s.to_string())),
+                s.parse::<ChessMove>()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_uci_move_checks_legality() {
+        let game = game::Game::new();
+        assert_eq!(
+            Ok(ChessMove {
+                from: (7, 1),
+                to: (6, 3),
+                promotion: None,
+            }),
+            game.parse_uci_move("g1f3")
+        );
+        assert_eq!(
+            Err(UciMoveError::IllegalMove(ChessMove {
+                from: (5, 2),
+                to: (5, 5),
+                promotion: None,
+            })),
+            game.parse_uci_move("e2e5")
+        );
+        assert_eq!(
+            Err(UciMoveError::Malformed("e2e".to_string())),
+            game.parse_uci_move("e2e")
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::pieces::{piece, position};
+use crate::{board, chessmove, color, fen, game};
+use std::fmt;
+
+const HIGHLIGHT: &str = "\x1b[30;43m";
+const RESET: &str = "\x1b[0m";
+
+// Draws a board as a grid with rank and file labels, by default with ASCII letters from White's
+// side. `Board` and `Game` implement `Display` with the defaults.
+pub struct BoardDisplay<'a> {
+    board: &'a board::Board,
+    unicode: bool,
+    orientation: color::Color,
+    highlight: Option<chessmove::ChessMove>,
+}
+
+impl<'a> BoardDisplay<'a> {
+    pub fn new(board: &'a board::Board) -> Self {
+        Self {
+            board,
+            unicode: false,
+            orientation: color::Color::WHITE,
+            highlight: None,
+        }
+    }
+
+    // figurines instead of letters
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    // the side whose pieces are drawn at the bottom
+    pub fn orientation(mut self, orientation: color::Color) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    // marks the from and to squares of the move with ANSI colours, for terminals
+    pub fn highlight(mut self, mv: chessmove::ChessMove) -> Self {
+        self.highlight = Some(mv);
+        self
+    }
+
+    fn symbol(&self, square: Option<piece::ColoredPiece>) -> char {
+        let piece = match square {
+            None if self.unicode => return '·',
+            None => return '.',
+            Some(piece) => piece,
+        };
+        let symbols = match (self.unicode, piece.color) {
+            (false, color::Color::WHITE) => ['K', 'Q', 'R', 'B', 'N', 'P'],
+            (false, color::Color::BLACK) => ['k', 'q', 'r', 'b', 'n', 'p'],
+            (true, color::Color::WHITE) => ['♔', '♕', '♖', '♗', '♘', '♙'],
+            (true, color::Color::BLACK) => ['♚', '♛', '♜', '♝', '♞', '♟'],
+        };
+        match piece.piece {
+            piece::PieceEnum::KING => symbols[0],
+            piece::PieceEnum::QUEEN => symbols[1],
+            piece::PieceEnum::ROOK => symbols[2],
+            piece::PieceEnum::BISHOP => symbols[3],
+            piece::PieceEnum::KNIGHT => symbols[4],
+            piece::PieceEnum::PAWN => symbols[5],
+        }
+    }
+
+    fn is_highlighted(&self, file: u8, rank: u8) -> bool {
+        match self.highlight {
+            Some(mv) => mv.from == (file, rank) || mv.to == (file, rank),
+            None => false,
+        }
+    }
+}
+
+impl<'a> fmt::Display for BoardDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (ranks, files): (Vec<u8>, Vec<u8>) = match self.orientation {
+            color::Color::WHITE => ((1..=8).rev().collect(), (1..=8).collect()),
+            color::Color::BLACK => ((1..=8).collect(), (1..=8).rev().collect()),
+        };
+
+        for rank in &ranks {
+            write!(f, "{}", rank)?;
+            for file in &files {
+                let symbol = self.symbol(self.board.piece_at(position::Position(*file, *rank)));
+                if self.is_highlighted(*file, *rank) {
+                    write!(f, " {}{}{}", HIGHLIGHT, symbol, RESET)?;
+                } else {
+                    write!(f, " {}", symbol)?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        write!(f, " ")?;
+        for file in &files {
+            write!(f, " {}", (b'a' + file - 1) as char)?;
+        }
+        writeln!(f)
+    }
+}
+
+// Draws a game as its board followed by the rest of the state, with the notation FEN uses for
+// it. The options are those of `BoardDisplay`, and `Game` implements `Display` with the defaults.
+pub struct GameDisplay<'a> {
+    game: &'a game::Game,
+    board: BoardDisplay<'a>,
+}
+
+impl<'a> GameDisplay<'a> {
+    pub fn new(game: &'a game::Game) -> Self {
+        Self {
+            game,
+            board: BoardDisplay::new(game.board()),
+        }
+    }
+
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.board = self.board.unicode(unicode);
+        self
+    }
+
+    pub fn orientation(mut self, orientation: color::Color) -> Self {
+        self.board = self.board.orientation(orientation);
+        self
+    }
+
+    pub fn highlight(mut self, mv: chessmove::ChessMove) -> Self {
+        self.board = self.board.highlight(mv);
+        self
+    }
+}
+
+impl<'a> fmt::Display for GameDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let game = self.game;
+        let side_to_move = match game.side_to_move() {
+            color::Color::WHITE => "white",
+            color::Color::BLACK => "black",
+        };
+        write!(f, "{}", self.board)?;
+        writeln!(f, "side to move: {}", side_to_move)?;
+        writeln!(
+            f,
+            "castling: {}",
+            fen::castling(game.castling_rights_white(), game.castling_rights_black())
+        )?;
+        writeln!(f, "en passant: {}", fen::en_passant(*game.en_passant()))?;
+        writeln!(f, "half moves: {}", game.half_moves())?;
+        writeln!(f, "full moves: {}", game.full_moves())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game;
+
+    const AFTER_E4: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+
+    #[test]
+    fn ascii_from_white() {
+        let game = game::Game::from_fen(AFTER_E4).unwrap();
+        assert_eq!(
+            "8 r n b q k b n r\n\
+             7 p p p p p p p p\n\
+             6 . . . . . . . .\n\
+             5 . . . . . . . .\n\
+             4 . . . . P . . .\n\
+             3 . . . . . . . .\n\
+             2 P P P P . P P P\n\
+             1 R N B Q K B N R\n\
+             \x20 a b c d e f g h\n",
+            game.board().to_string()
+        );
+    }
+
+    #[test]
+    fn unicode_from_black() {
+        let game = game::Game::from_fen(AFTER_E4).unwrap();
+        let display = BoardDisplay::new(game.board())
+            .unicode(true)
+            .orientation(color::Color::BLACK);
+        assert_eq!(
+            "1 ♖ ♘ ♗ ♔ ♕ ♗ ♘ ♖\n\
+             2 ♙ ♙ ♙ · ♙ ♙ ♙ ♙\n\
+             3 · · · · · · · ·\n\
+             4 · · · ♙ · · · ·\n\
+             5 · · · · · · · ·\n\
+             6 · · · · · · · ·\n\
+             7 ♟ ♟ ♟ ♟ ♟ ♟ ♟ ♟\n\
+             8 ♜ ♞ ♝ ♚ ♛ ♝ ♞ ♜\n\
+             \x20 h g f e d c b a\n",
+            display.to_string()
+        );
+    }
+
+    #[test]
+    fn highlighted_move() {
+        let game = game::Game::from_fen(AFTER_E4).unwrap();
+        let mv = "e2e4".parse().unwrap();
+        let text = BoardDisplay::new(game.board()).highlight(mv).to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!("4 . . . . \x1b[30;43mP\x1b[0m . . .", lines[4]);
+        assert_eq!("2 P P P P \x1b[30;43m.\x1b[0m P P P", lines[6]);
+        assert_eq!(2, text.matches(RESET).count());
+    }
+
+    #[test]
+    fn game_with_options() {
+        let game = game::Game::from_fen(AFTER_E4).unwrap();
+        let text = GameDisplay::new(&game)
+            .unicode(true)
+            .orientation(color::Color::BLACK)
+            .highlight("e2e4".parse().unwrap())
+            .to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!("1 ♖ ♘ ♗ ♔ ♕ ♗ ♘ ♖", lines[0]);
+        assert_eq!("4 · · · \x1b[30;43m♙\x1b[0m · · · ·", lines[3]);
+        assert_eq!(
+            [
+                "side to move: black",
+                "castling: KQkq",
+                "en passant: e3",
+                "half moves: 0",
+                "full moves: 1",
+            ],
+            lines[9..]
+        );
+    }
+}
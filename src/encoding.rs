@@ -0,0 +1,431 @@
+use crate::pieces::position;
+use crate::{board, color, fen, game};
+use std::convert::TryFrom;
+use std::{error, fmt};
+
+// Version 1 of the binary format of `Game::encode`:
+//
+// - a header byte with the version
+// - the occupied squares as a little endian u64, a1 is the lowest bit and h8 the highest
+// - a 4-bit code for each occupied square from a1 to h8, two to a byte with the first in the
+//   low half and the last half byte zero if there is an odd number of pieces. The code is the
+//   piece (1 pawn, 2 rook, 3 knight, 4 bishop, 5 queen, 6 king) plus 8 for black pieces.
+// - the flags as a varint: castling rights in bits 0 to 3 (white kingside, white queenside,
+//   black kingside, black queenside), black to move in bit 4 and the en passant file (1 to 8,
+//   0 for none) in bits 5 to 8. Without en passant they fit in a single byte.
+// - the half move clock and the full move number as varints
+//
+// Varints are LEB128, seven bits to a byte starting with the lowest.
+pub const VERSION: u8 = 1;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodeError {
+    UnsupportedVersion(u8),
+    UnexpectedEnd,
+    // the number of bytes left after the position
+    TrailingBytes(usize),
+    InvalidPieceCode(u8),
+    // longer than needed for a u16 or larger than one
+    InvalidVarint,
+    InvalidFlags(u16),
+    // zero, which FEN does not allow either
+    InvalidFullMoves(u16),
+    InvalidKingCount(color::Color),
+    PawnOnBackRank,
+    InvalidCastlingRights,
+    InvalidEnPassant,
+    InvalidBoard(crate::error::Error),
+    OpponentInCheck,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "version {} is not supported", version)
+            }
+            DecodeError::UnexpectedEnd => write!(f, "the encoded position ends too early"),
+            DecodeError::TrailingBytes(n) => write!(f, "{} bytes after the position", n),
+            DecodeError::InvalidPieceCode(code) => write!(f, "{} is not a valid piece code", code),
+            DecodeError::InvalidVarint => write!(f, "a counter or the flags do not fit 16 bits"),
+            DecodeError::InvalidFlags(flags) => write!(f, "invalid flags {:#x}", flags),
+            DecodeError::InvalidFullMoves(n) => write!(f, "invalid fullmove number '{}'", n),
+            DecodeError::InvalidKingCount(color) => {
+                write!(f, "{:?} must have exactly one king", color)
+            }
+            DecodeError::PawnOnBackRank => {
+                write!(f, "pawns cannot stand on the first or last rank")
+            }
+            DecodeError::InvalidCastlingRights => {
+                write!(f, "castling rights without king and rook on their squares")
+            }
+            DecodeError::InvalidEnPassant => write!(f, "en passant without a pawn to capture"),
+            DecodeError::InvalidBoard(e) => write!(f, "{}", e),
+            DecodeError::OpponentInCheck => write!(f, "the side that is not to move is in check"),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+pub(crate) struct Decoded {
+    pub board: board::Board,
+    pub white_king: position::Position,
+    pub black_king: position::Position,
+    pub side_to_move: color::Color,
+    pub castling_rights_white: (bool, bool),
+    pub castling_rights_black: (bool, bool),
+    pub en_passant: Option<position::Position>,
+    pub half_moves: u16,
+    pub full_moves: u16,
+}
+
+// the u8 board lists the squares file by file, the encoding rank by rank
+fn u8_board_index(square: usize) -> usize {
+    square % 8 * 8 + square / 8
+}
+
+pub(crate) fn encode(game: &game::Game) -> Vec<u8> {
+    let u8_board = game.board().to_u8_board();
+    let codes = (0..64)
+        .map(|square| u8_board[u8_board_index(square)])
+        .collect::<Vec<_>>();
+
+    let mut bytes = vec![VERSION];
+    let occupancy = codes
+        .iter()
+        .enumerate()
+        .filter(|(_, code)| **code != 0)
+        .fold(0u64, |acc, (square, _)| acc | 1 << square);
+    bytes.extend_from_slice(&occupancy.to_le_bytes());
+
+    let nibbles = codes
+        .iter()
+        .filter(|code| **code != 0)
+        .map(|code| match code {
+            11..=16 => code - 10 + 8,
+            _ => *code,
+        })
+        .collect::<Vec<_>>();
+    for pair in nibbles.chunks(2) {
+        bytes.push(pair[0] | pair.get(1).map_or(0, |high| high << 4));
+    }
+
+    let (white, black) = (game.castling_rights_white(), game.castling_rights_black());
+    let mut flags = 0;
+    for (bit, right) in [white.0, white.1, black.0, black.1].iter().enumerate() {
+        if *right {
+            flags |= 1 << bit;
+        }
+    }
+    if *game.side_to_move() == color::Color::BLACK {
+        flags |= 1 << 4;
+    }
+    if let Some(ep) = game.en_passant() {
+        flags |= (ep.0 as u16) << 5;
+    }
+
+    for value in &[flags, game.half_moves(), game.full_moves()] {
+        write_varint(&mut bytes, *value);
+    }
+    bytes
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<Decoded, DecodeError> {
+    let mut reader = Reader { bytes, offset: 0 };
+
+    match reader.byte()? {
+        VERSION => (),
+        version => return Err(DecodeError::UnsupportedVersion(version)),
+    }
+
+    let mut occupancy = [0; 8];
+    for byte in occupancy.iter_mut() {
+        *byte = reader.byte()?;
+    }
+    let occupancy = u64::from_le_bytes(occupancy);
+
+    let mut u8_board = [0; 64];
+    let mut pair = 0;
+    let mut kings = (0, 0);
+    for (i, square) in (0..64).filter(|i| occupancy & 1 << i != 0).enumerate() {
+        let nibble = match i % 2 {
+            0 => {
+                pair = reader.byte()?;
+                pair & 0xf
+            }
+            _ => pair >> 4,
+        };
+        let code = match nibble {
+            1..=6 => nibble,
+            9..=14 => nibble - 8 + 10,
+            _ => return Err(DecodeError::InvalidPieceCode(nibble)),
+        };
+        match code {
+            6 => kings.0 += 1,
+            16 => kings.1 += 1,
+            1 | 11 if !(8..56).contains(&square) => return Err(DecodeError::PawnOnBackRank),
+            _ => (),
+        }
+        u8_board[u8_board_index(square)] = code;
+    }
+    if occupancy.count_ones() % 2 == 1 && pair >> 4 != 0 {
+        return Err(DecodeError::InvalidPieceCode(pair >> 4));
+    }
+    match kings {
+        (1, 1) => (),
+        (1, _) => return Err(DecodeError::InvalidKingCount(color::Color::BLACK)),
+        _ => return Err(DecodeError::InvalidKingCount(color::Color::WHITE)),
+    }
+
+    let flags = reader.varint()?;
+    let half_moves = reader.varint()?;
+    let full_moves = reader.varint()?;
+    if reader.offset < bytes.len() {
+        return Err(DecodeError::TrailingBytes(bytes.len() - reader.offset));
+    }
+    if full_moves == 0 {
+        return Err(DecodeError::InvalidFullMoves(full_moves));
+    }
+
+    if flags >> 9 != 0 {
+        return Err(DecodeError::InvalidFlags(flags));
+    }
+    let right = |bit: u16| flags & 1 << bit != 0;
+    let castling_rights_white = (right(0), right(1));
+    let castling_rights_black = (right(2), right(3));
+    if !fen::castling_rights_fit_board(castling_rights_white, castling_rights_black, &u8_board) {
+        return Err(DecodeError::InvalidCastlingRights);
+    }
+
+    let side_to_move = match right(4) {
+        false => color::Color::WHITE,
+        true => color::Color::BLACK,
+    };
+    let en_passant = match (flags >> 5) as u8 {
+        0 => None,
+        file @ 1..=8 => {
+            let square = match side_to_move {
+                color::Color::WHITE => position::Position(file, 6),
+                color::Color::BLACK => position::Position(file, 3),
+            };
+            if !fen::en_passant_fits_board(square, side_to_move, &u8_board) {
+                return Err(DecodeError::InvalidEnPassant);
+            }
+            Some(square)
+        }
+        _ => return Err(DecodeError::InvalidFlags(flags)),
+    };
+
+    let (board, white_king, black_king) =
+        board::Board::try_from_u8_board(&u8_board).map_err(DecodeError::InvalidBoard)?;
+    if fen::opponent_in_check(&board, white_king, black_king, side_to_move) {
+        return Err(DecodeError::OpponentInCheck);
+    }
+
+    Ok(Decoded {
+        board,
+        white_king,
+        black_king,
+        side_to_move,
+        castling_rights_white,
+        castling_rights_black,
+        en_passant,
+        half_moves,
+        full_moves,
+    })
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u16) {
+    while value >= 0x80 {
+        bytes.push(value as u8 & 0x7f | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self
+            .bytes
+            .get(self.offset)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    // a u16 takes at most three bytes, of which the last carries two bits
+    fn varint(&mut self) -> Result<u16, DecodeError> {
+        let mut value = 0u32;
+        for shift in &[0, 7, 14] {
+            let byte = self.byte()?;
+            // a zero byte after the first one could have been left out
+            if byte == 0 && *shift > 0 {
+                return Err(DecodeError::InvalidVarint);
+            }
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return u16::try_from(value).map_err(|_| DecodeError::InvalidVarint);
+            }
+        }
+        Err(DecodeError::InvalidVarint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITIONS: [&str; 6] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 3",
+        "8/8/8/KPp4r/8/8/8/7k w - - 300 1000",
+        "4k3/8/8/8/8/8/8/4K3 b - - 65535 65535",
+    ];
+
+    #[test]
+    fn round_trip() {
+        for fen in POSITIONS.iter() {
+            let game = game::Game::from_fen(fen).unwrap();
+            let decoded = game::Game::decode(&game.encode()).unwrap();
+            assert_eq!(*fen, decoded.to_fen());
+            assert_eq!(game.hash(), decoded.hash());
+        }
+    }
+
+    #[test]
+    fn round_trip_after_moves() {
+        let mut game = game::Game::from_fen(POSITIONS[1]).unwrap();
+        for mv in game.legal_moves() {
+            game.make_move(mv);
+            for reply in game.legal_moves() {
+                game.make_move(reply);
+                assert_eq!(
+                    game.to_fen(),
+                    game::Game::decode(&game.encode()).unwrap().to_fen()
+                );
+                game.undo_last_move();
+            }
+            game.undo_last_move();
+        }
+    }
+
+    #[test]
+    fn layout_of_initial_position() {
+        let bytes = game::Game::new().encode();
+        assert_eq!(28, bytes.len());
+        assert_eq!(VERSION, bytes[0]);
+        assert_eq!(
+            0xffff_0000_0000_ffff,
+            u64::from_le_bytes([
+                bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7], bytes[8]
+            ])
+        );
+        // rook and knight on a1 and b1, then the black pawns on a7 and b7
+        assert_eq!(0x32, bytes[9]);
+        assert_eq!(0x99, bytes[17]);
+        // all castling rights, white to move, no en passant
+        assert_eq!([0x0f, 0, 1], bytes[25..]);
+
+        let game = game::Game::from_fen(POSITIONS[3]).unwrap();
+        let bytes = game.encode();
+        assert_eq!([0x99, 0x01, 0, 3], bytes[bytes.len() - 4..]);
+    }
+
+    #[test]
+    fn large_counters_do_not_overflow() {
+        let game = game::Game::from_fen(POSITIONS[5]).unwrap();
+        let bytes = game.encode();
+        assert_eq!(
+            [0x10, 0xff, 0xff, 0x03, 0xff, 0xff, 0x03],
+            bytes[bytes.len() - 7..]
+        );
+        let decoded = game::Game::decode(&bytes).unwrap();
+        assert_eq!(65535, decoded.half_moves());
+        assert_eq!(65535, decoded.full_moves());
+    }
+
+    #[test]
+    fn rejects_invalid_encodings() {
+        let bytes = game::Game::new().encode();
+        let decode = |bytes: &[u8]| game::Game::decode(bytes).err();
+        let changed = |index: usize, byte: u8| {
+            let mut bytes = bytes.clone();
+            bytes[index] = byte;
+            bytes
+        };
+
+        assert_eq!(None, decode(&bytes));
+        assert_eq!(
+            Some(DecodeError::UnsupportedVersion(2)),
+            decode(&changed(0, 2))
+        );
+        assert_eq!(Some(DecodeError::UnexpectedEnd), decode(&bytes[..27]));
+        assert_eq!(Some(DecodeError::UnexpectedEnd), decode(&[]));
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(Some(DecodeError::TrailingBytes(1)), decode(&longer));
+
+        // the white queen and king on d1 and e1 share byte 11
+        assert_eq!(
+            Some(DecodeError::InvalidPieceCode(7)),
+            decode(&changed(11, 0x75))
+        );
+        assert_eq!(
+            Some(DecodeError::InvalidKingCount(color::Color::WHITE)),
+            decode(&changed(11, 0x55))
+        );
+        // a pawn instead of the rook on a1
+        assert_eq!(Some(DecodeError::PawnOnBackRank), decode(&changed(9, 0x31)));
+        // the a1 rook and b1 knight swapped with castling rights left
+        assert_eq!(
+            Some(DecodeError::InvalidCastlingRights),
+            decode(&changed(9, 0x23))
+        );
+
+        // en passant on a6 without a black pawn on a5
+        assert_eq!(
+            Some(DecodeError::InvalidEnPassant),
+            decode(&changed(25, 0x2f))
+        );
+
+        // white to move and in check, then black to move while white is in check
+        let in_check = game::Game::from_fen("4k3/4r3/8/8/8/8/8/4K3 w - - 0 1")
+            .unwrap()
+            .encode();
+        let mut black_to_move = in_check.clone();
+        black_to_move[in_check.len() - 3] |= 0x10;
+        assert_eq!(None, decode(&in_check));
+        assert_eq!(Some(DecodeError::OpponentInCheck), decode(&black_to_move));
+
+        let with_tail = |tail: &[u8]| {
+            let mut bytes = bytes[..25].to_vec();
+            bytes.extend_from_slice(tail);
+            bytes
+        };
+        assert_eq!(None, decode(&with_tail(&[0x0f, 0, 1])));
+        assert_eq!(
+            Some(DecodeError::InvalidFullMoves(0)),
+            decode(&with_tail(&[0x0f, 0, 0]))
+        );
+        assert_eq!(
+            Some(DecodeError::InvalidVarint),
+            decode(&with_tail(&[0x0f, 0x80, 0, 1]))
+        );
+        assert_eq!(
+            Some(DecodeError::InvalidVarint),
+            decode(&with_tail(&[0x0f, 0x80, 0x80, 0x04, 1]))
+        );
+        assert_eq!(
+            Some(DecodeError::InvalidFlags(0x20f)),
+            decode(&with_tail(&[0x8f, 0x04, 0, 1]))
+        );
+    }
+}
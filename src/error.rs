@@ -0,0 +1,30 @@
+use crate::chessmove;
+use crate::color;
+use crate::pieces::position;
+use std::{error, fmt};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Error {
+    IllegalMove(chessmove::ChessMove),
+    NoHistory,
+    EmptySquare(position::Position),
+    InvalidPieceCode(u8),
+    MissingKing(color::Color),
+    // the game array has the wrong length or describes an impossible en passant square
+    MalformedGameArray,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::IllegalMove(mv) => write!(f, "{} is not a legal move", mv),
+            Error::NoHistory => write!(f, "there is no move to undo"),
+            Error::EmptySquare(position) => write!(f, "no piece at position {:?}", position),
+            Error::InvalidPieceCode(code) => write!(f, "{} is not a valid piece code", code),
+            Error::MissingKing(color) => write!(f, "no {:?} king on the board", color),
+            Error::MalformedGameArray => write!(f, "malformed game array"),
+        }
+    }
+}
+
+impl error::Error for Error {}
@@ -0,0 +1,481 @@
+use crate::pieces::position;
+use crate::{attack, board, color, game};
+use std::{error, fmt};
+
+pub const INITIAL_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FenError {
+    WrongNumberOfFields(usize),
+    WrongNumberOfRanks(usize),
+    InvalidRank(u8),
+    InvalidPiece(char),
+    PawnOnBackRank,
+    InvalidKingCount(color::Color),
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassant(String),
+    InvalidHalfMoves(String),
+    InvalidFullMoves(String),
+    InvalidBoard(crate::error::Error),
+    OpponentInCheck,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongNumberOfFields(n) => {
+                write!(f, "expected 4 or 6 space separated fields, found {}", n)
+            }
+            FenError::WrongNumberOfRanks(n) => write!(f, "expected 8 ranks, found {}", n),
+            FenError::InvalidRank(rank) => write!(f, "rank {} does not describe 8 squares", rank),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece character '{}'", c),
+            FenError::PawnOnBackRank => write!(f, "pawns cannot stand on the first or last rank"),
+            FenError::InvalidKingCount(color) => {
+                write!(f, "{:?} must have exactly one king", color)
+            }
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move '{}'", s),
+            FenError::InvalidCastlingRights(s) => write!(f, "invalid castling rights '{}'", s),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en passant square '{}'", s),
+            FenError::InvalidHalfMoves(s) => write!(f, "invalid halfmove clock '{}'", s),
+            FenError::InvalidFullMoves(s) => write!(f, "invalid fullmove number '{}'", s),
+            FenError::InvalidBoard(e) => write!(f, "{}", e),
+            FenError::OpponentInCheck => write!(f, "the side that is not to move is in check"),
+        }
+    }
+}
+
+impl error::Error for FenError {}
+
+type CastlingRights = (bool, bool);
+
+pub(crate) struct Fen {
+    pub board: board::Board,
+    pub white_king: position::Position,
+    pub black_king: position::Position,
+    pub side_to_move: color::Color,
+    pub castling_rights_white: CastlingRights,
+    pub castling_rights_black: CastlingRights,
+    pub en_passant: Option<position::Position>,
+    pub half_moves: u16,
+    pub full_moves: u16,
+}
+
+pub(crate) fn parse(fen: &str) -> Result<Fen, FenError> {
+    let fields = fen.split_whitespace().collect::<Vec<_>>();
+    if fields.len() != 4 && fields.len() != 6 {
+        return Err(FenError::WrongNumberOfFields(fields.len()));
+    }
+
+    let u8_board = parse_placement(fields[0])?;
+    let (board, white_king, black_king) =
+        board::Board::try_from_u8_board(&u8_board).map_err(FenError::InvalidBoard)?;
+
+    let side_to_move = match fields[1] {
+        "w" => color::Color::WHITE,
+        "b" => color::Color::BLACK,
+        s => return Err(FenError::InvalidSideToMove(s.to_string())),
+    };
+
+    if opponent_in_check(&board, white_king, black_king, side_to_move) {
+        return Err(FenError::OpponentInCheck);
+    }
+
+    let (castling_rights_white, castling_rights_black) = parse_castling(fields[2], &u8_board)?;
+
+    let en_passant = parse_en_passant(fields[3], side_to_move, &u8_board)?;
+
+    let (half_moves, full_moves) = if fields.len() == 6 {
+        let half_moves = fields[4]
+            .parse::<u16>()
+            .map_err(|_| FenError::InvalidHalfMoves(fields[4].to_string()))?;
+        let full_moves = match fields[5].parse::<u16>() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(FenError::InvalidFullMoves(fields[5].to_string())),
+        };
+        (half_moves, full_moves)
+    } else {
+        (0, 1)
+    };
+
+    Ok(Fen {
+        board,
+        white_king,
+        black_king,
+        side_to_move,
+        castling_rights_white,
+        castling_rights_black,
+        en_passant,
+        half_moves,
+        full_moves,
+    })
+}
+
+pub(crate) fn format(game: &game::Game) -> String {
+    format!(
+        "{} {} {} {} {} {}",
+        placement(game.board()),
+        side_to_move(*game.side_to_move()),
+        castling(game.castling_rights_white(), game.castling_rights_black()),
+        en_passant(*game.en_passant()),
+        game.half_moves(),
+        game.full_moves()
+    )
+}
+
+pub(crate) fn side_to_move(side_to_move: color::Color) -> char {
+    match side_to_move {
+        color::Color::WHITE => 'w',
+        color::Color::BLACK => 'b',
+    }
+}
+
+// "KQkq" with the rights that are left, or "-"
+pub(crate) fn castling(white: CastlingRights, black: CastlingRights) -> String {
+    let castling = [
+        (white.0, 'K'),
+        (white.1, 'Q'),
+        (black.0, 'k'),
+        (black.1, 'q'),
+    ]
+    .iter()
+    .filter(|(right, _)| *right)
+    .map(|(_, c)| *c)
+    .collect::<String>();
+    if castling.is_empty() {
+        "-".to_string()
+    } else {
+        castling
+    }
+}
+
+pub(crate) fn en_passant(en_passant: Option<position::Position>) -> String {
+    match en_passant {
+        None => "-".to_string(),
+        Some(ep) => square_name(ep),
+    }
+}
+
+// the first field of a FEN
+pub(crate) fn placement(board: &board::Board) -> String {
+    let u8_board = board.to_u8_board();
+    let mut fen = String::new();
+
+    for rank in (1..=8).rev() {
+        let mut empty = 0;
+        for file in 1..=8 {
+            let code = u8_board[index(file, rank)];
+            if code == 0 {
+                empty += 1;
+                continue;
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+                empty = 0;
+            }
+            fen.push(code_to_char(code));
+        }
+        if empty > 0 {
+            fen.push_str(&empty.to_string());
+        }
+        if rank > 1 {
+            fen.push('/');
+        }
+    }
+    fen
+}
+
+// squares off the board, which a `ChessMove` can hold, are written as their coordinates
+pub(crate) fn square_name(position: position::Position) -> String {
+    if !(1..=8).contains(&position.0) || !(1..=8).contains(&position.1) {
+        return format!("{:?}", (position.0, position.1));
+    }
+    format!(
+        "{}{}",
+        (b'a' + position.0 - 1) as char,
+        (b'1' + position.1 - 1) as char
+    )
+}
+
+pub(crate) fn parse_square(s: &str) -> Option<position::Position> {
+    let bytes = s.as_bytes();
+    if bytes.len() != 2 {
+        return None;
+    }
+    match (bytes[0], bytes[1]) {
+        (file @ b'a'..=b'h', rank @ b'1'..=b'8') => {
+            Some(position::Position(file - b'a' + 1, rank - b'1' + 1))
+        }
+        _ => None,
+    }
+}
+
+fn index(file: u8, rank: u8) -> usize {
+    (file as usize - 1) * 8 + rank as usize - 1
+}
+
+fn code_to_char(code: u8) -> char {
+    let c = match code % 10 {
+        1 => 'p',
+        2 => 'r',
+        3 => 'n',
+        4 => 'b',
+        5 => 'q',
+        _ => 'k',
+    };
+    if code > 10 {
+        c
+    } else {
+        c.to_ascii_uppercase()
+    }
+}
+
+fn char_to_code(c: char) -> Option<u8> {
+    let code = match c.to_ascii_lowercase() {
+        'p' => 1,
+        'r' => 2,
+        'n' => 3,
+        'b' => 4,
+        'q' => 5,
+        'k' => 6,
+        _ => return None,
+    };
+    if c.is_ascii_lowercase() {
+        Some(code + 10)
+    } else {
+        Some(code)
+    }
+}
+
+pub(crate) fn parse_placement(placement: &str) -> Result<[u8; 64], FenError> {
+    let ranks = placement.split('/').collect::<Vec<_>>();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongNumberOfRanks(ranks.len()));
+    }
+
+    let mut u8_board = [0; 64];
+
+    for (i, rank_str) in ranks.iter().enumerate() {
+        let rank = 8 - i as u8;
+        let mut file = 1;
+        for c in rank_str.chars() {
+            if let Some(empty) = c.to_digit(10) {
+                if empty == 0 || empty > 8 {
+                    return Err(FenError::InvalidRank(rank));
+                }
+                file += empty as u8;
+            } else {
+                let code = char_to_code(c).ok_or(FenError::InvalidPiece(c))?;
+                if file > 8 {
+                    return Err(FenError::InvalidRank(rank));
+                }
+                if code % 10 == 1 && (rank == 1 || rank == 8) {
+                    return Err(FenError::PawnOnBackRank);
+                }
+                u8_board[index(file, rank)] = code;
+                file += 1;
+            }
+            if file > 9 {
+                return Err(FenError::InvalidRank(rank));
+            }
+        }
+        if file != 9 {
+            return Err(FenError::InvalidRank(rank));
+        }
+    }
+
+    if u8_board.iter().filter(|code| **code == 6).count() != 1 {
+        return Err(FenError::InvalidKingCount(color::Color::WHITE));
+    }
+    if u8_board.iter().filter(|code| **code == 16).count() != 1 {
+        return Err(FenError::InvalidKingCount(color::Color::BLACK));
+    }
+
+    Ok(u8_board)
+}
+
+fn parse_castling(
+    castling: &str,
+    u8_board: &[u8; 64],
+) -> Result<(CastlingRights, CastlingRights), FenError> {
+    let error = || FenError::InvalidCastlingRights(castling.to_string());
+
+    if castling == "-" {
+        return Ok(((false, false), (false, false)));
+    }
+
+    let mut white = (false, false);
+    let mut black = (false, false);
+
+    for c in castling.chars() {
+        let right = match c {
+            'K' => &mut white.0,
+            'Q' => &mut white.1,
+            'k' => &mut black.0,
+            'q' => &mut black.1,
+            _ => return Err(error()),
+        };
+        if *right {
+            return Err(error());
+        }
+        *right = true;
+    }
+
+    if !castling_rights_fit_board(white, black, u8_board) {
+        return Err(error());
+    }
+    Ok((white, black))
+}
+
+// whether the side to move could capture the other king, which no legal position allows
+pub(crate) fn opponent_in_check(
+    board: &board::Board,
+    white_king: position::Position,
+    black_king: position::Position,
+    side_to_move: color::Color,
+) -> bool {
+    let king = match side_to_move {
+        color::Color::WHITE => black_king,
+        color::Color::BLACK => white_king,
+    };
+    attack::attacker_squares(board, king, side_to_move) != 0
+}
+
+// whether the king and the rook of every castling right are still on their initial squares
+pub(crate) fn castling_rights_fit_board(
+    white: CastlingRights,
+    black: CastlingRights,
+    u8_board: &[u8; 64],
+) -> bool {
+    [
+        (white.0, 6, 2, 8, 1),
+        (white.1, 6, 2, 1, 1),
+        (black.0, 16, 12, 8, 8),
+        (black.1, 16, 12, 1, 8),
+    ]
+    .iter()
+    .all(|&(right, king, rook, rook_file, rank)| {
+        !right || (u8_board[index(5, rank)] == king && u8_board[index(rook_file, rank)] == rook)
+    })
+}
+
+fn parse_en_passant(
+    en_passant: &str,
+    side_to_move: color::Color,
+    u8_board: &[u8; 64],
+) -> Result<Option<position::Position>, FenError> {
+    if en_passant == "-" {
+        return Ok(None);
+    }
+
+    let error = || FenError::InvalidEnPassant(en_passant.to_string());
+
+    let square = parse_square(en_passant).ok_or_else(error)?;
+    if !en_passant_fits_board(square, side_to_move, u8_board) {
+        return Err(error());
+    }
+
+    Ok(Some(square))
+}
+
+// whether the side to move could take a pawn en passant on `square`: it is empty and the
+// opponent's pawn stands in front of it
+pub(crate) fn en_passant_fits_board(
+    square: position::Position,
+    side_to_move: color::Color,
+    u8_board: &[u8; 64],
+) -> bool {
+    let (ep_rank, pawn_rank, pawn) = match side_to_move {
+        color::Color::WHITE => (6, 5, 11),
+        color::Color::BLACK => (3, 4, 1),
+    };
+    square.1 == ep_rank
+        && u8_board[index(square.0, square.1)] == 0
+        && u8_board[index(square.0, pawn_rank)] == pawn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initial_position_from_fen() {
+        let game = game::Game::from_fen(INITIAL_FEN).unwrap();
+        assert_eq!(game::Game::new().to_game_arr()[..], game.to_game_arr()[..]);
+    }
+
+    #[test]
+    fn initial_position_to_fen() {
+        assert_eq!(INITIAL_FEN, game::Game::new().to_fen());
+    }
+
+    #[test]
+    fn fen_without_clocks() {
+        let game = game::Game::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+        assert_eq!(color::Color::BLACK, *game.side_to_move());
+        assert_eq!(0, game.half_moves());
+        assert_eq!(1, game.full_moves());
+    }
+
+    #[test]
+    fn clocks_above_255() {
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 300 1000";
+        let game = game::Game::from_fen(fen).unwrap();
+        assert_eq!(300, game.half_moves());
+        assert_eq!(1000, game.full_moves());
+        assert_eq!(fen, game.to_fen());
+    }
+
+    #[test]
+    fn en_passant_round_trip() {
+        let fen = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3";
+        let game = game::Game::from_fen(fen).unwrap();
+        assert_eq!(Some(position::Position(5, 3)), *game.en_passant());
+        assert_eq!(fen, game.to_fen());
+    }
+
+    #[test]
+    fn invalid_fens() {
+        let cases = [
+            (
+                "8/8/8/8/8/8/8/8 w - -",
+                FenError::InvalidKingCount(color::Color::WHITE),
+            ),
+            ("4k3/8/8/8/8/8/8/4K3 w", FenError::WrongNumberOfFields(2)),
+            ("4k3/8/8/8/8/8/4K3 w - -", FenError::WrongNumberOfRanks(7)),
+            ("4k3/9/8/8/8/8/8/4K3 w - -", FenError::InvalidRank(7)),
+            ("4k3/8/8/8/8/8/8/4K4 w - -", FenError::InvalidRank(1)),
+            ("4k3/8/8/8/8/8/8/4X3 w - -", FenError::InvalidPiece('X')),
+            ("4k2P/8/8/8/8/8/8/4K3 w - -", FenError::PawnOnBackRank),
+            (
+                "4k3/8/8/8/8/8/8/4K3 x - -",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w K -",
+                FenError::InvalidCastlingRights("K".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - e3",
+                FenError::InvalidEnPassant("e3".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - a 1",
+                FenError::InvalidHalfMoves("a".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+                FenError::InvalidFullMoves("0".to_string()),
+            ),
+            ("4k3/4R3/8/8/8/8/8/4K3 w - - 0 1", FenError::OpponentInCheck),
+            ("4k3/8/8/8/8/5n2/8/4K3 b - - 0 1", FenError::OpponentInCheck),
+        ];
+
+        for (fen, expected) in cases.iter() {
+            match game::Game::from_fen(fen) {
+                Ok(_) => panic!("{} should not parse", fen),
+                Err(e) => assert_eq!(*expected, e, "{}", fen),
+            }
+        }
+    }
+}
@@ -1,6 +1,15 @@
-use super::pieces::{piece, position, relative_position};
-use super::{attack, attack::AttackedBoard, board, chessmove, color};
-
+#[cfg(feature = "bitboard")]
+use super::bitboard;
+use super::pieces::relative_position;
+use super::pieces::{piece, piece::Piece, position};
+use super::{
+    attack, board, chessmove, color, display, encoding, error, fen, outcome, san, square, zobrist,
+};
+use std::convert::TryFrom;
+use std::fmt;
+
+// everything needed to take back a move, the board itself is changed back in place
+#[derive(Clone)]
 pub struct PreviousGameState {
     en_passant: Option<position::Position>,
     side_to_move: color::Color,
@@ -8,9 +17,19 @@ pub struct PreviousGameState {
     castling_rights_black: (bool, bool),
     half_moves: u16,
     full_moves: u16,
-    u8_board: [u8; 64],
+    white_king: position::Position,
+    black_king: position::Position,
+    hash: u64,
+    chessmove: chessmove::ChessMove,
+    // the piece as it stood on the from square, i.e. a pawn for promotions
+    moved_piece: piece::ColoredPiece,
+    // the square differs from the move's target for en passant
+    captured: Option<(piece::ColoredPiece, position::Position)>,
+    // en passant square if the capture was legal, which is what counts for repetitions
+    legal_en_passant: Option<position::Position>,
 }
 
+#[derive(Clone)]
 pub struct Game {
     board: board::Board,
     en_passant: Option<position::Position>,
@@ -21,9 +40,23 @@ pub struct Game {
     full_moves: u16,
     white_king: position::Position,
     black_king: position::Position,
+    hash: u64,
     previous_game_states: Vec<PreviousGameState>,
 }
 
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the board followed by the rest of the state, see `display::GameDisplay` for the options
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", display::GameDisplay::new(self))
+    }
+}
+
 impl Game {
     pub fn new() -> Self {
         Self {
@@ -36,17 +69,21 @@ impl Game {
             full_moves: 1,
             white_king: position::Position(5, 1),
             black_king: position::Position(5, 8),
+            hash: 0,
             previous_game_states: vec![],
         }
+        .with_hash()
     }
 
     pub fn undo_last_move(&mut self) {
-        if self.previous_game_states.is_empty() {
+        if self.try_undo_last_move().is_err() {
             panic!("No more previous game states");
         }
+    }
 
+    pub fn try_undo_last_move(&mut self) -> Result<(), error::Error> {
         let previous_game_state = match self.previous_game_states.pop() {
-            None => panic!("No more previous game states"),
+            None => return Err(error::Error::NoHistory),
             Some(v) => v,
         };
 
@@ -56,26 +93,60 @@ impl Game {
         self.full_moves = previous_game_state.full_moves;
         self.half_moves = previous_game_state.half_moves;
         self.side_to_move = previous_game_state.side_to_move;
+        self.white_king = previous_game_state.white_king;
+        self.black_king = previous_game_state.black_king;
+        self.hash = previous_game_state.hash;
 
-        let (board, white_king, black_king) =
-            board::Board::from_u8_board(&previous_game_state.u8_board);
-        self.board = board;
-        self.white_king = white_king;
-        self.black_king = black_king;
+        unmake_move(&mut self.board, &previous_game_state);
+        Ok(())
     }
 
     pub fn make_move(&mut self, mv: chessmove::ChessMove) {
-        if !self.legal_moves().contains(&mv) {
+        if self.try_make_move(mv).is_err() {
             panic!("Not a legal move");
         }
+    }
 
-        self.add_previous_game_state();
+    pub fn try_make_move(&mut self, mv: chessmove::ChessMove) -> Result<(), error::Error> {
+        let legal_moves = self.legal_moves();
+        if !legal_moves.contains(&mv) {
+            return Err(error::Error::IllegalMove(mv));
+        }
 
         let piece = self
             .board
             .take_piece(position::Position((mv.from).0, (mv.from).1));
 
-        if piece.piece() == piece::PieceEnum::PAWN {
+        let to = position::Position((mv.to).0, (mv.to).1);
+        let captured_square = match self.en_passant {
+            Some(ep) if ep == to && piece.piece == piece::PieceEnum::PAWN => {
+                position::Position((mv.to).0, (mv.from).1)
+            }
+            _ => to,
+        };
+        let captured = self
+            .board
+            .piece_at(captured_square)
+            .map(|captured| (captured, captured_square));
+
+        self.add_previous_game_state(mv, piece, captured, self.legal_en_passant(&legal_moves));
+
+        // castling rights and en passant are hashed back in once they are updated
+        self.hash ^= zobrist::piece(piece, position::Position((mv.from).0, (mv.from).1))
+            ^ zobrist::castling(self.castling_rights_white, self.castling_rights_black)
+            ^ zobrist::en_passant(self.en_passant)
+            ^ zobrist::side(color::Color::BLACK);
+        if let Some((captured, captured_square)) = captured {
+            self.hash ^= zobrist::piece(captured, captured_square);
+        }
+
+        if captured.is_some() || piece.piece == piece::PieceEnum::PAWN {
+            self.half_moves = 0;
+        } else {
+            self.half_moves += 1;
+        }
+
+        if piece.piece == piece::PieceEnum::PAWN {
             match ((mv.from).1, (mv.to).1) {
                 (2, 4) => self.en_passant = Some(position::Position((mv.to).0, 3)),
                 (7, 5) => self.en_passant = Some(position::Position((mv.to).0, 6)),
@@ -99,7 +170,7 @@ impl Game {
             self.en_passant = None;
         }
 
-        if piece.piece() == piece::PieceEnum::KING {
+        if piece.piece == piece::PieceEnum::KING {
             match self.side_to_move {
                 color::Color::WHITE => {
                     self.white_king = position::Position((mv.to).0, (mv.to).1);
@@ -145,14 +216,17 @@ impl Game {
 
         let position = position::Position((mv.to).0, (mv.to).1);
 
-        let mut piece = match mv.promotion {
+        let piece = match mv.promotion {
             None => piece,
-            Some(promotion_piece) => {
-                piece::promotion_piece_to_piece(promotion_piece, self.side_to_move, position)
-            }
+            Some(promotion_piece) => piece::ColoredPiece {
+                piece: piece::promotion_piece_to_piece_enum(promotion_piece),
+                color: self.side_to_move,
+            },
         };
-        piece.set_position(&position);
-        self.board.set_square(Some(piece), position);
+        self.board.set_piece(position, Some(piece));
+        self.hash ^= zobrist::piece(piece, position)
+            ^ zobrist::castling(self.castling_rights_white, self.castling_rights_black)
+            ^ zobrist::en_passant(self.en_passant);
 
         match self.side_to_move {
             color::Color::BLACK => {
@@ -163,11 +237,24 @@ impl Game {
                 self.side_to_move = color::Color::BLACK;
             }
         }
+        Ok(())
     }
 
-    fn add_previous_game_state(&mut self) {
+    fn add_previous_game_state(
+        &mut self,
+        chessmove: chessmove::ChessMove,
+        moved_piece: piece::ColoredPiece,
+        captured: Option<(piece::ColoredPiece, position::Position)>,
+        legal_en_passant: Option<position::Position>,
+    ) {
         self.previous_game_states.push(PreviousGameState {
-            u8_board: self.board.to_u8_board(),
+            chessmove,
+            moved_piece,
+            captured,
+            legal_en_passant,
+            white_king: self.white_king,
+            black_king: self.black_king,
+            hash: self.hash,
             castling_rights_black: self.castling_rights_black,
             castling_rights_white: self.castling_rights_white,
             en_passant: self.en_passant,
@@ -178,26 +265,53 @@ impl Game {
     }
 
     fn move_piece(&mut self, from: position::Position, to: position::Position) {
-        let mut pc = self.board.take_piece(from);
-        let position = to;
-        pc.set_position(&position);
-        self.board.set_square(Some(pc), position);
+        let pc = self.board.take_piece(from);
+        self.board.set_piece(to, Some(pc));
+        self.hash ^= zobrist::piece(pc, from) ^ zobrist::piece(pc, to);
     }
 
     fn remove_piece(&mut self, sqr: position::Position) {
         self.board.take_piece(sqr);
     }
 
+    // The legacy 73 byte format, which stores each move counter in a single byte. `encode` and
+    // `decode` replace it, this is kept to read positions stored in it.
     pub fn from_game_arr(game_arr: &[u8]) -> Self {
+        match Self::try_from_game_arr(game_arr) {
+            Ok(game) => game,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_from_game_arr(game_arr: &[u8]) -> Result<Self, error::Error> {
+        if game_arr.len() != 73 {
+            return Err(error::Error::MalformedGameArray);
+        }
+
         let mut board_slice: [u8; 64] = [0; 64];
         board_slice.copy_from_slice(&game_arr[0..64]);
 
-        let (board, white_king, black_king) = board::Board::from_u8_board(&board_slice);
+        let (board, white_king, black_king) = board::Board::try_from_u8_board(&board_slice)?;
 
-        let en_passant: Option<position::Position> = if game_arr[64] == 0 {
-            None
+        let side_to_move = if game_arr[72] == 0 {
+            color::Color::WHITE
         } else {
-            Some(position::Position(game_arr[64], game_arr[65]))
+            color::Color::BLACK
+        };
+
+        let en_passant: Option<position::Position> = match (game_arr[64], game_arr[65]) {
+            (0, _) => None,
+            (file, rank)
+                if (1..=8).contains(&file)
+                    && fen::en_passant_fits_board(
+                        position::Position(file, rank),
+                        side_to_move,
+                        &board_slice,
+                    ) =>
+            {
+                Some(position::Position(file, rank))
+            }
+            (_, _) => return Err(error::Error::MalformedGameArray),
         };
         let castling_rights_white = if game_arr[66] == 1 && game_arr[67] == 1 {
             (true, true)
@@ -219,16 +333,18 @@ impl Game {
             (false, false)
         };
 
+        if !fen::castling_rights_fit_board(
+            castling_rights_white,
+            castling_rights_black,
+            &board_slice,
+        ) {
+            return Err(error::Error::MalformedGameArray);
+        }
+
         let half_moves = game_arr[70];
         let full_moves = game_arr[71];
 
-        let side_to_move = if game_arr[72] == 0 {
-            color::Color::WHITE
-        } else {
-            color::Color::BLACK
-        };
-
-        Self {
+        Ok(Self {
             board,
             en_passant,
             castling_rights_white,
@@ -238,8 +354,10 @@ impl Game {
             full_moves: full_moves as u16,
             white_king,
             black_king,
+            hash: 0,
             previous_game_states: vec![],
         }
+        .with_hash())
     }
 
     pub fn to_game_arr(&self) -> [u8; 73] {
@@ -271,6 +389,53 @@ impl Game {
         game_arr
     }
 
+    // the compact binary format described in `encoding`
+    pub fn encode(&self) -> Vec<u8> {
+        encoding::encode(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, encoding::DecodeError> {
+        let decoded = encoding::decode(bytes)?;
+
+        Ok(Self {
+            board: decoded.board,
+            en_passant: decoded.en_passant,
+            castling_rights_white: decoded.castling_rights_white,
+            castling_rights_black: decoded.castling_rights_black,
+            side_to_move: decoded.side_to_move,
+            half_moves: decoded.half_moves,
+            full_moves: decoded.full_moves,
+            white_king: decoded.white_king,
+            black_king: decoded.black_king,
+            hash: 0,
+            previous_game_states: vec![],
+        }
+        .with_hash())
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, fen::FenError> {
+        let fen = fen::parse(fen)?;
+
+        Ok(Self {
+            board: fen.board,
+            en_passant: fen.en_passant,
+            castling_rights_white: fen.castling_rights_white,
+            castling_rights_black: fen.castling_rights_black,
+            side_to_move: fen.side_to_move,
+            half_moves: fen.half_moves,
+            full_moves: fen.full_moves,
+            white_king: fen.white_king,
+            black_king: fen.black_king,
+            hash: 0,
+            previous_game_states: vec![],
+        }
+        .with_hash())
+    }
+
+    pub fn to_fen(&self) -> String {
+        fen::format(self)
+    }
+
     pub fn board(&self) -> &board::Board {
         &self.board
     }
@@ -299,6 +464,32 @@ impl Game {
         self.full_moves
     }
 
+    // zobrist key of the position, kept up to date by make_move and undo_last_move
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // the en passant file is part of the key whenever an en passant square is set
+    pub fn recompute_hash(&self) -> u64 {
+        let mut hash = zobrist::side(self.side_to_move)
+            ^ zobrist::castling(self.castling_rights_white, self.castling_rights_black)
+            ^ zobrist::en_passant(self.en_passant);
+        for file in 1..=8 {
+            for rank in 1..=8 {
+                let position = position::Position(file, rank);
+                if let Some(piece) = self.board.piece_at(position) {
+                    hash ^= zobrist::piece(piece, position);
+                }
+            }
+        }
+        hash
+    }
+
+    fn with_hash(mut self) -> Self {
+        self.hash = self.recompute_hash();
+        self
+    }
+
     pub fn current_king_position(&self) -> position::Position {
         match self.side_to_move() {
             color::Color::WHITE => self.white_king,
@@ -306,203 +497,654 @@ impl Game {
         }
     }
 
-    pub fn legal_moves(&self) -> Vec<chessmove::ChessMove> {
-        let other_side = match self.side_to_move() {
+    pub fn move_to_san(&self, mv: &chessmove::ChessMove) -> String {
+        san::format(self, mv)
+    }
+
+    pub fn parse_san(&self, san: &str) -> Result<chessmove::ChessMove, san::SanError> {
+        san::parse(self, san)
+    }
+
+    pub fn parse_uci_move(
+        &self,
+        uci: &str,
+    ) -> Result<chessmove::ChessMove, chessmove::UciMoveError> {
+        let mv = uci.parse::<chessmove::ChessMove>()?;
+        if self.legal_moves().contains(&mv) {
+            Ok(mv)
+        } else {
+            Err(chessmove::UciMoveError::IllegalMove(mv))
+        }
+    }
+
+    pub fn is_check(&self) -> bool {
+        self.checker_squares() != 0
+    }
+
+    pub fn is_double_check(&self) -> bool {
+        self.checker_squares().count_ones() > 1
+    }
+
+    // the squares of the pieces giving check to the side to move
+    pub fn checkers(&self) -> Vec<square::Square> {
+        let checkers = self.checker_squares();
+        square::Square::all()
+            .filter(|square| checkers & 1 << square.index() != 0)
+            .collect()
+    }
+
+    fn checker_squares(&self) -> u64 {
+        let opponent = match self.side_to_move {
             color::Color::WHITE => color::Color::BLACK,
             color::Color::BLACK => color::Color::WHITE,
         };
-        let attacked_board =
-            attack::get_attacked_squares(self.board(), other_side, self.current_king_position());
-        let king_position = self.current_king_position();
-        let king_square_attackers =
-            &attacked_board[king_position.0 as usize - 1][king_position.1 as usize - 1];
+        attack::attacker_squares(&self.board, self.current_king_position(), opponent)
+    }
 
-        let king = match self.board().get_square(king_position) {
-            Some(king) => king,
-            _ => panic!("No King found at king position"),
+    // whether the legal move `mv` checks the opponent, without making it. Besides the moved
+    // piece, a slider can give check through a square that the move empties.
+    pub fn gives_check(&self, mv: &chessmove::ChessMove) -> bool {
+        let (from, to) = match (
+            square::Square::try_from(mv.from),
+            square::Square::try_from(mv.to),
+        ) {
+            (Ok(from), Ok(to)) => (position::Position::from(from), position::Position::from(to)),
+            // like a move from an empty square, a move off the board checks nothing
+            _ => return false,
+        };
+        let piece = match self.board.piece_at(from) {
+            Some(piece) => piece,
+            None => return false,
+        };
+        let king = match self.side_to_move {
+            color::Color::WHITE => self.black_king,
+            color::Color::BLACK => self.white_king,
+        };
+        let moved = match mv.promotion {
+            None => piece,
+            Some(promotion_piece) => piece::ColoredPiece {
+                piece: piece::promotion_piece_to_piece_enum(promotion_piece),
+                color: piece.color,
+            },
         };
 
-        if king_square_attackers.len() > 1 {
-            self.king_moves(king, &attacked_board)
-        } else {
-            let mut moves: Vec<chessmove::ChessMove> = vec![];
-            for piece in self.board.pieces_of_color_except_king(*self.side_to_move()) {
-                moves.append(&mut (piece.moves(&self.board, king_position, &self.en_passant)));
+        // the captured pawn of an en passant capture, or the rook's squares when castling
+        let (vacated, rook) = match piece.piece {
+            piece::PieceEnum::PAWN if self.en_passant == Some(to) => {
+                (Some(position::Position(to.0, from.1)), None)
+            }
+            piece::PieceEnum::KING if from.0 + 2 == to.0 => (
+                Some(position::Position(from.0 + 3, from.1)),
+                Some(position::Position(from.0 + 1, from.1)),
+            ),
+            piece::PieceEnum::KING if to.0 + 2 == from.0 => (
+                Some(position::Position(from.0 - 4, from.1)),
+                Some(position::Position(from.0 - 1, from.1)),
+            ),
+            _ => (None, None),
+        };
+
+        let file_diff = king.0 as i8 - to.0 as i8;
+        let rank_diff = king.1 as i8 - to.1 as i8;
+        let forward = match self.side_to_move {
+            color::Color::WHITE => 1,
+            color::Color::BLACK => -1,
+        };
+        let direct = match moved.piece {
+            piece::PieceEnum::PAWN => rank_diff == forward && file_diff.abs() == 1,
+            piece::PieceEnum::KNIGHT => {
+                let distance = (file_diff.abs(), rank_diff.abs());
+                distance == (1, 2) || distance == (2, 1)
             }
+            _ => false,
+        };
+        if direct {
+            return true;
+        }
 
-            if king_square_attackers.is_empty() {
-                moves.append(&mut self.king_moves(king, &attacked_board));
-                moves
+        let occupant = |position: position::Position| {
+            if position == to {
+                Some(moved)
+            } else if Some(position) == rook {
+                Some(piece::ColoredPiece {
+                    piece: piece::PieceEnum::ROOK,
+                    color: self.side_to_move,
+                })
+            } else if position == from || Some(position) == vacated {
+                None
             } else {
-                let attacker = king_square_attackers[0];
-                if attacker.piece() == piece::PieceEnum::KNIGHT {
-                    moves = moves
-                        .into_iter()
-                        .filter(|mv| {
-                            (mv.to).0 == attacker.position().0 && (mv.to).1 == attacker.position().1
-                        })
-                        .collect::<Vec<_>>();
-                    moves.append(&mut self.king_moves(king, &attacked_board));
-                    moves
-                } else if attacker.piece() == piece::PieceEnum::PAWN {
-                    moves = moves
-                        .into_iter()
-                        .filter(|mv| {
-                            if let Some(en_passant) = self.en_passant {
-                                match self
-                                    .board()
-                                    .get_square(position::Position((mv.from).0, (mv.from).1))
-                                {
-                                    None => panic!(),
-                                    Some(piece) => {
-                                        if piece.piece() == piece::PieceEnum::PAWN {
-                                            ((mv.to).0 == en_passant.0 && (mv.to).1 == en_passant.1)
-                                                || ((mv.to).0 == attacker.position().0
-                                                    && (mv.to).1 == attacker.position().1)
-                                        } else {
-                                            (mv.to).0 == attacker.position().0
-                                                && (mv.to).1 == attacker.position().1
-                                        }
-                                    }
-                                }
-                            } else {
-                                (mv.to).0 == attacker.position().0
-                                    && (mv.to).1 == attacker.position().1
-                            }
-                        })
-                        .collect::<Vec<_>>();
-                    moves.append(&mut self.king_moves(king, &attacked_board));
-                    moves
-                } else {
-                    let (mover, _) = match relative_position::get_line_to_other_piece(
-                        king.position(),
-                        attacker.position(),
-                    ) {
-                        None => panic!(),
-                        Some(v) => v,
+                self.board.piece_at(position)
+            }
+        };
+
+        // looks from the king through the squares the move fills or empties for a slider
+        [Some(to), rook, Some(from), vacated]
+            .iter()
+            .flatten()
+            .any(|square| {
+                let (step, direction) =
+                    match relative_position::get_line_to_other_piece(&king, square) {
+                        Some(line) => line,
+                        None => return false,
                     };
-                    let mut allowed_positions = vec![];
+                let sliders = match direction {
+                    relative_position::Direction::STRAIGHT(_) => {
+                        [piece::PieceEnum::ROOK, piece::PieceEnum::QUEEN]
+                    }
+                    relative_position::Direction::DIAGONAL(_) => {
+                        [piece::PieceEnum::BISHOP, piece::PieceEnum::QUEEN]
+                    }
+                };
+                let (mut file, mut rank) = (king.0 as i8 + step.0, king.1 as i8 + step.1);
+                while (1..=8).contains(&file) && (1..=8).contains(&rank) {
+                    if let Some(found) = occupant(position::Position(file as u8, rank as u8)) {
+                        return found.color == self.side_to_move && sliders.contains(&found.piece);
+                    }
+                    file += step.0;
+                    rank += step.1;
+                }
+                false
+            })
+    }
+
+    // draws that have to be claimed are only reported by `claim_draw`
+    pub fn outcome(&self) -> Option<outcome::Outcome> {
+        let mut moves = chessmove::MoveList::new();
+        let checkers = self.generate_moves_into(&mut moves);
+        if moves.is_empty() {
+            return if checkers != 0 {
+                let winner = match self.side_to_move {
+                    color::Color::WHITE => color::Color::BLACK,
+                    color::Color::BLACK => color::Color::WHITE,
+                };
+                Some(outcome::Outcome::Win {
+                    winner,
+                    reason: outcome::Reason::Checkmate,
+                })
+            } else {
+                Some(outcome::Outcome::Draw {
+                    reason: outcome::Reason::Stalemate,
+                })
+            };
+        }
 
-                    let mut new_file = king.position().0 as i8;
-                    let mut new_rank = king.position().1 as i8;
+        let reason = if self.is_insufficient_material() {
+            outcome::Reason::InsufficientMaterial
+        } else if self.is_seventy_five_move_draw() {
+            outcome::Reason::SeventyFiveMoveRule
+        } else if self.repetitions(self.legal_en_passant(&moves)) >= 5 {
+            outcome::Reason::FivefoldRepetition
+        } else {
+            return None;
+        };
+        Some(outcome::Outcome::Draw { reason })
+    }
 
-                    loop {
-                        new_file += mover.0;
-                        new_rank += mover.1;
+    // the outcome if the side to move claims a draw wherever the rules allow it
+    pub fn claim_draw(&self) -> Option<outcome::Outcome> {
+        if let Some(outcome) = self.outcome() {
+            return Some(outcome);
+        }
 
-                        let new_position = position::Position(new_file as u8, new_rank as u8);
+        let reason = if self.can_claim_fifty_move_draw() {
+            outcome::Reason::FiftyMoveRule
+        } else if self.can_claim_threefold() {
+            outcome::Reason::ThreefoldRepetition
+        } else {
+            return None;
+        };
+        Some(outcome::Outcome::Draw { reason })
+    }
 
-                        allowed_positions.push(new_position);
-                        if self.board.get_square(new_position).is_some() {
-                            break;
-                        }
-                    }
+    // fifty moves by each side without a pawn move or a capture
+    pub fn can_claim_fifty_move_draw(&self) -> bool {
+        self.half_moves >= 100
+    }
 
-                    moves = moves
-                        .into_iter()
-                        .filter(|mv| {
-                            allowed_positions.contains(&position::Position((mv.to).0, (mv.to).1))
-                        })
-                        .collect::<Vec<_>>();
+    // seventy-five moves by each side without a pawn move or a capture end the game without a claim
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.half_moves >= 150
+    }
 
-                    moves.append(&mut self.king_moves(king, &attacked_board));
-                    moves
-                }
+    // how often the current position occurred in this game, including now. Positions are equal
+    // if they have the same placement, side to move, castling rights and legal en passant capture.
+    pub fn repetition_count(&self) -> usize {
+        // moves are only generated right after a double pawn push, to see if it can be taken
+        let legal_en_passant = match self.en_passant {
+            None => None,
+            Some(_) => {
+                let mut moves = chessmove::MoveList::new();
+                self.generate_legal_moves(&mut moves);
+                self.legal_en_passant(&moves)
+            }
+        };
+        self.repetitions(legal_en_passant)
+    }
+
+    // `repetition_count` for a caller that already knows the legal en passant capture
+    fn repetitions(&self, legal_en_passant: Option<position::Position>) -> usize {
+        // the hash covers the en passant square even when it cannot be captured, so it is left
+        // out and the legal capture compared instead
+        let hash = self.hash ^ zobrist::en_passant(self.en_passant);
+
+        // positions before the last pawn move or capture cannot come back
+        let mut board = self.board;
+        let mut count = 1;
+        for state in self
+            .previous_game_states
+            .iter()
+            .rev()
+            .take(self.half_moves as usize)
+        {
+            unmake_move(&mut board, state);
+            if state.hash ^ zobrist::en_passant(state.en_passant) == hash
+                && state.legal_en_passant == legal_en_passant
+                && state.side_to_move == self.side_to_move
+                && state.castling_rights_white == self.castling_rights_white
+                && state.castling_rights_black == self.castling_rights_black
+                && board == self.board
+            {
+                count += 1;
             }
         }
+        count
     }
 
-    fn king_moves(
-        &self,
-        king: &Box<dyn piece::Piece>,
-        attacked_board: &AttackedBoard,
-    ) -> Vec<chessmove::ChessMove> {
-        if king.piece() != piece::PieceEnum::KING {
-            panic!("Given piece is not a king");
+    pub fn can_claim_threefold(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.repetition_count() >= 5
+    }
+
+    fn legal_en_passant(&self, legal_moves: &[chessmove::ChessMove]) -> Option<position::Position> {
+        let ep = self.en_passant?;
+        let is_capture = legal_moves.iter().any(|mv| {
+            position::Position((mv.to).0, (mv.to).1) == ep
+                && match self
+                    .board
+                    .get_square(position::Position((mv.from).0, (mv.from).1))
+                {
+                    Some(piece) => piece.piece() == piece::PieceEnum::PAWN,
+                    None => false,
+                }
+        });
+        if is_capture {
+            Some(ep)
+        } else {
+            None
         }
+    }
 
-        let moves = king.moves(self.board(), *king.position(), &None);
+    // neither side can checkmate, no matter how badly the other one plays
+    pub fn is_insufficient_material(&self) -> bool {
+        !self.has_mating_material(color::Color::WHITE)
+            && !self.has_mating_material(color::Color::BLACK)
+    }
 
-        let mut moves = moves
-            .into_iter()
-            .filter(|mv| square_safe(&position::Position((mv.to).0, (mv.to).1), attacked_board))
-            .collect::<Vec<_>>();
+    // whether some sequence of legal moves lets `color` checkmate, e.g. to adjudicate a flag fall
+    pub fn has_mating_material(&self, color: color::Color) -> bool {
+        let other_color = match color {
+            color::Color::WHITE => color::Color::BLACK,
+            color::Color::BLACK => color::Color::WHITE,
+        };
+        let own_pieces = self.board.pieces_of_color_except_king(color);
+        let other_pieces = self.board.pieces_of_color_except_king(other_color);
+
+        let is_minor = |piece: &piece::PlacedPiece| {
+            piece.piece() == piece::PieceEnum::KNIGHT || piece.piece() == piece::PieceEnum::BISHOP
+        };
+        if !own_pieces.iter().all(is_minor) {
+            return true;
+        }
+        if own_pieces.is_empty() {
+            return false;
+        }
 
-        if square_under_attack(king.position(), &attacked_board) {
-            return moves;
+        // bishops that all stand on squares of one colour can never attack the other colour
+        let square_color =
+            |piece: &piece::PlacedPiece| (piece.position().0 + piece.position().1) % 2;
+        let first_square_color = square_color(&own_pieces[0]);
+        if own_pieces.iter().chain(other_pieces.iter()).all(|piece| {
+            piece.piece() == piece::PieceEnum::BISHOP && square_color(piece) == first_square_color
+        }) {
+            return false;
         }
 
-        let castling_rights = match self.side_to_move {
-            color::Color::BLACK => self.castling_rights_black,
-            color::Color::WHITE => self.castling_rights_white,
+        // a single minor piece needs something of the other side to trap its king with
+        own_pieces.len() > 1 || !other_pieces.is_empty()
+    }
+
+    pub fn move_history(&self) -> Vec<chessmove::ChessMove> {
+        self.previous_game_states
+            .iter()
+            .map(|state| state.chessmove)
+            .collect()
+    }
+
+    // the position before the first move in the history, without any history itself
+    pub(crate) fn initial_position(&self) -> Self {
+        let state = match self.previous_game_states.first() {
+            None => return self.copy_position(),
+            Some(state) => state,
         };
 
-        if castling_rights.0 && self.can_castle_kingside(king, attacked_board) {
-            moves.push(chessmove::ChessMove {
-                from: (king.position().0, king.position().1),
-                to: (king.position().0 + 2, king.position().1),
-                promotion: None,
-            });
+        let mut board = self.board;
+        for state in self.previous_game_states.iter().rev() {
+            unmake_move(&mut board, state);
         }
 
-        if castling_rights.1 && self.can_castle_queenside(king, attacked_board) {
-            moves.push(chessmove::ChessMove {
-                from: (king.position().0, king.position().1),
-                to: (king.position().0 - 2, king.position().1),
-                promotion: None,
-            });
+        Self {
+            board,
+            en_passant: state.en_passant,
+            side_to_move: state.side_to_move,
+            castling_rights_white: state.castling_rights_white,
+            castling_rights_black: state.castling_rights_black,
+            half_moves: state.half_moves,
+            full_moves: state.full_moves,
+            white_king: state.white_king,
+            black_king: state.black_king,
+            hash: state.hash,
+            previous_game_states: vec![],
         }
+    }
 
+    pub(crate) fn copy_position(&self) -> Self {
+        Self {
+            board: self.board,
+            en_passant: self.en_passant,
+            side_to_move: self.side_to_move,
+            castling_rights_white: self.castling_rights_white,
+            castling_rights_black: self.castling_rights_black,
+            half_moves: self.half_moves,
+            full_moves: self.full_moves,
+            white_king: self.white_king,
+            black_king: self.black_king,
+            hash: self.hash,
+            previous_game_states: vec![],
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<chessmove::ChessMove> {
+        let mut moves = chessmove::MoveList::new();
+        self.generate_legal_moves(&mut moves);
+        moves.to_vec()
+    }
+
+    // replaces the contents of `moves` with the legal moves, without allocating
+    pub fn generate_legal_moves(&self, moves: &mut chessmove::MoveList) {
+        moves.clear();
+        self.generate_moves_into(moves);
+    }
+
+    // returns the squares of the pieces giving check, as `checker_squares` would
+    fn generate_moves_into(&self, moves: &mut impl chessmove::MoveSink) -> u64 {
+        #[cfg(feature = "bitboard")]
+        return bitboard::Bitboards::from_game(self).generate_moves_into(moves);
+
+        #[cfg(not(feature = "bitboard"))]
+        self.generate_legal_moves_from_board(moves)
+    }
+
+    // the legal moves with the moved and captured pieces, as the move generator found them
+    pub fn legal_moves_detailed(&self) -> Vec<chessmove::MoveInfo> {
+        let mut moves = vec![];
+        self.generate_moves_into(&mut moves);
         moves
     }
 
-    fn is_empty_square(&self, position: &position::Position) -> bool {
-        self.board.get_square(*position).is_none()
+    // the bitboard generator is tested against this one
+    #[cfg(all(test, feature = "bitboard"))]
+    pub(crate) fn legal_moves_from_board(&self) -> Vec<chessmove::ChessMove> {
+        let mut moves = chessmove::MoveList::new();
+        self.generate_legal_moves_from_board(&mut moves);
+        moves.to_vec()
     }
 
-    fn can_castle_kingside(
+    #[cfg(all(test, feature = "bitboard"))]
+    pub(crate) fn legal_moves_detailed_from_board(&self) -> Vec<chessmove::MoveInfo> {
+        let mut moves = vec![];
+        self.generate_legal_moves_from_board(&mut moves);
+        moves
+    }
+
+    #[cfg(any(test, not(feature = "bitboard")))]
+    fn generate_legal_moves_from_board(&self, moves: &mut impl chessmove::MoveSink) -> u64 {
+        let king_position = self.current_king_position();
+        let opponent = match self.side_to_move {
+            color::Color::WHITE => color::Color::BLACK,
+            color::Color::BLACK => color::Color::WHITE,
+        };
+        let checkers = attack::attacker_squares(&self.board, king_position, opponent);
+
+        if checkers.count_ones() < 2 {
+            // with a single checker, the other pieces have to capture it or block its line
+            let (targets, pawn_targets) = match checkers {
+                0 => (!0, !0),
+                _ => {
+                    let checker = position::Position::from_bit_index(checkers.trailing_zeros());
+                    let targets = checkers | self.squares_between(king_position, checker);
+                    match (self.board.piece_at(checker), self.en_passant) {
+                        (Some(piece), Some(ep)) if piece.piece == piece::PieceEnum::PAWN => {
+                            (targets, targets | ep.bit())
+                        }
+                        _ => (targets, targets),
+                    }
+                }
+            };
+
+            // pins are worked out once, then every piece only looks up its own ray
+            let pins = attack::pin_rays(&self.board, king_position, self.side_to_move);
+            let mut piece_moves = chessmove::MoveList::new();
+            for file in 1..=8 {
+                for rank in 1..=8 {
+                    let position = position::Position(file, rank);
+                    let piece = match self.board.piece_at(position) {
+                        Some(piece)
+                            if piece.color == self.side_to_move
+                                && piece.piece != piece::PieceEnum::KING =>
+                        {
+                            piece
+                        }
+                        _ => continue,
+                    };
+                    let mut targets = match piece.piece {
+                        piece::PieceEnum::PAWN => pawn_targets,
+                        _ => targets,
+                    };
+                    let pin = pins[position.bit().trailing_zeros() as usize];
+                    if pin != 0 {
+                        targets &= pin;
+                    }
+
+                    piece_moves.clear();
+                    piece.placed_at(position).generate_moves_ignoring_pins(
+                        &self.board,
+                        &self.en_passant,
+                        king_position,
+                        &mut piece_moves,
+                    );
+                    piece_moves.retain_from(0, |mv| {
+                        targets & position::Position((mv.to).0, (mv.to).1).bit() != 0
+                    });
+                    for mv in &piece_moves {
+                        moves.add(*mv, || self.board_move_info(*mv, piece.piece));
+                    }
+                }
+            }
+        }
+
+        self.generate_king_moves(king_position, opponent, checkers == 0, moves);
+        checkers
+    }
+
+    // the squares strictly between two squares on a line, an empty set if they are not on one
+    #[cfg(any(test, not(feature = "bitboard")))]
+    fn squares_between(&self, from: position::Position, to: position::Position) -> u64 {
+        let (mover, _) = match relative_position::get_line_to_other_piece(&from, &to) {
+            None => return 0,
+            Some(v) => v,
+        };
+
+        let mut squares = 0;
+        let mut current = from;
+        loop {
+            current = position::Position(
+                (current.0 as i8 + mover.0) as u8,
+                (current.1 as i8 + mover.1) as u8,
+            );
+            if current == to {
+                return squares;
+            }
+            squares |= current.bit();
+        }
+    }
+
+    #[cfg(any(test, not(feature = "bitboard")))]
+    fn generate_king_moves(
         &self,
-        king: &Box<dyn piece::Piece>,
-        attacked_board: &AttackedBoard,
-    ) -> bool {
-        let one_right_of_king = position::Position(king.position().0 + 1, king.position().1);
-        let two_right_of_king = position::Position(king.position().0 + 2, king.position().1);
+        king_position: position::Position,
+        opponent: color::Color,
+        may_castle: bool,
+        moves: &mut impl chessmove::MoveSink,
+    ) {
+        // the king must not step back along the line of a slider checking it
+        let mut without_king = self.board;
+        without_king.set_piece(king_position, None);
+        let safe = |position: position::Position| {
+            attack::attacker_squares(&without_king, position, opponent) == 0
+        };
+
+        let king = piece::ColoredPiece {
+            piece: piece::PieceEnum::KING,
+            color: self.side_to_move,
+        }
+        .placed_at(king_position);
+        let mut king_moves = chessmove::MoveList::new();
+        king.generate_moves_ignoring_pins(&self.board, &None, king_position, &mut king_moves);
+        for mv in &king_moves {
+            if safe(position::Position((mv.to).0, (mv.to).1)) {
+                moves.add(*mv, || self.board_move_info(*mv, piece::PieceEnum::KING));
+            }
+        }
+
+        if !may_castle {
+            return;
+        }
 
-        self.is_empty_square(&one_right_of_king)
-            && self.is_empty_square(&two_right_of_king)
-            && square_safe(&one_right_of_king, attacked_board)
-            && square_safe(&two_right_of_king, attacked_board)
+        let castling_rights = match self.side_to_move {
+            color::Color::BLACK => self.castling_rights_black,
+            color::Color::WHITE => self.castling_rights_white,
+        };
+        let square = |offset: i8| {
+            position::Position((king_position.0 as i8 + offset) as u8, king_position.1)
+        };
+        let empty = |offset: i8| self.board.is_empty(square(offset));
+
+        let castle = |to_file: u8, kind: chessmove::MoveKind| {
+            let chessmove = chessmove::ChessMove {
+                from: (king_position.0, king_position.1),
+                to: (to_file, king_position.1),
+                promotion: None,
+            };
+            let info = chessmove::MoveInfo {
+                chessmove,
+                piece: piece::PieceEnum::KING,
+                captured: None,
+                kind,
+            };
+            (chessmove, info)
+        };
+
+        if castling_rights.0 && empty(1) && empty(2) && safe(square(1)) && safe(square(2)) {
+            let (mv, info) = castle(king_position.0 + 2, chessmove::MoveKind::CastleKingside);
+            moves.add(mv, || info);
+        }
+
+        if castling_rights.1
+            && empty(-1)
+            && empty(-2)
+            && empty(-3)
+            && safe(square(-1))
+            && safe(square(-2))
+        {
+            let (mv, info) = castle(king_position.0 - 2, chessmove::MoveKind::CastleQueenside);
+            moves.add(mv, || info);
+        }
     }
 
-    fn can_castle_queenside(
+    // the details of a move of `piece` found by the board generator, other than castling
+    #[cfg(any(test, not(feature = "bitboard")))]
+    fn board_move_info(
         &self,
-        king: &Box<dyn piece::Piece>,
-        attacked_board: &AttackedBoard,
-    ) -> bool {
-        let one_left_of_king = position::Position(king.position().0 - 1, king.position().1);
-        let two_left_of_king = position::Position(king.position().0 - 2, king.position().1);
-        let three_left_king = position::Position(king.position().0 - 3, king.position().1);
-
-        self.is_empty_square(&one_left_of_king)
-            && self.is_empty_square(&two_left_of_king)
-            && self.is_empty_square(&three_left_king)
-            && square_safe(&one_left_of_king, attacked_board)
-            && square_safe(&two_left_of_king, attacked_board)
+        mv: chessmove::ChessMove,
+        piece: piece::PieceEnum,
+    ) -> chessmove::MoveInfo {
+        let from = position::Position((mv.from).0, (mv.from).1);
+        let to = position::Position((mv.to).0, (mv.to).1);
+        let square = |position: position::Position| square::Square::try_from(position).ok();
+        let captured = self
+            .board
+            .piece_at(to)
+            .and_then(|captured| Some((captured.piece, square(to)?)));
+
+        let (kind, captured) = match piece {
+            piece::PieceEnum::PAWN if mv.promotion.is_some() => {
+                (chessmove::MoveKind::Promotion, captured)
+            }
+            piece::PieceEnum::PAWN if from.0 != to.0 && captured.is_none() => (
+                chessmove::MoveKind::EnPassant,
+                square(position::Position(to.0, from.1))
+                    .map(|square| (piece::PieceEnum::PAWN, square)),
+            ),
+            piece::PieceEnum::PAWN if (from.1 as i8 - to.1 as i8).abs() == 2 => {
+                (chessmove::MoveKind::DoublePawnPush, None)
+            }
+            _ => (chessmove::MoveKind::Normal, captured),
+        };
+
+        chessmove::MoveInfo {
+            chessmove: mv,
+            piece,
+            captured,
+            kind,
+        }
     }
 }
 
-fn square_safe(
-    position: &position::Position,
-    attacked_board: &[std::vec::Vec<std::vec::Vec<&std::boxed::Box<dyn piece::Piece>>>],
-) -> bool {
-    attacked_board[position.0 as usize - 1][position.1 as usize - 1].is_empty()
+// puts the pieces back where they stood before the move of `state` was made
+fn unmake_move(board: &mut board::Board, state: &PreviousGameState) {
+    let mv = state.chessmove;
+    let from = position::Position((mv.from).0, (mv.from).1);
+    let to = position::Position((mv.to).0, (mv.to).1);
+
+    board.set_piece(to, None);
+    board.set_piece(from, Some(state.moved_piece));
+    if let Some((piece, position)) = state.captured {
+        board.set_piece(position, Some(piece));
+    }
+
+    if state.moved_piece.piece == piece::PieceEnum::KING {
+        let (rook_from, rook_to) = if from.0 + 2 == to.0 {
+            (from.0 + 3, from.0 + 1)
+        } else if from.0 == to.0 + 2 {
+            (from.0 - 4, from.0 - 1)
+        } else {
+            return;
+        };
+        let rook = board.take_piece(position::Position(rook_to, from.1));
+        board.set_piece(position::Position(rook_from, from.1), Some(rook));
+    }
 }
 
-fn square_under_attack(
-    position: &position::Position,
-    attacked_board: &[std::vec::Vec<std::vec::Vec<&std::boxed::Box<dyn piece::Piece>>>],
-) -> bool {
-    !square_safe(position, attacked_board)
+// plays moves given in SAN, for the tests of this and other modules
+#[cfg(test)]
+pub(crate) fn play(game: &mut Game, moves: &[&str]) {
+    for san in moves {
+        let mv = game.parse_san(san).unwrap();
+        game.make_move(mv);
+    }
 }
 
 #[cfg(test)]
@@ -516,6 +1158,28 @@ mod tests {
         2, 1, 0, 0, 0, 0, 11, 12, 0, 0, 1, 1, 1, 1, 0, 1, 0,
     ];
 
+    #[test]
+    fn display_shows_board_and_state() {
+        let game = Game::from_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 0 12").unwrap();
+        assert_eq!(
+            "8 r . . . k . . r\n\
+             7 . . . . . . . .\n\
+             6 . . . . . . . .\n\
+             5 . . . p P . . .\n\
+             4 . . . . . . . .\n\
+             3 . . . . . . . .\n\
+             2 . . . . . . . .\n\
+             1 R . . . K . . R\n\
+             \x20 a b c d e f g h\n\
+             side to move: white\n\
+             castling: Kq\n\
+             en passant: d6\n\
+             half moves: 0\n\
+             full moves: 12\n",
+            game.to_string()
+        );
+    }
+
     #[test]
     fn from_game_arr_initial_board() {
         let actual_game = Game::from_game_arr(&INITIAL_GAME_ARR);
@@ -825,7 +1489,7 @@ mod tests {
             .set_square(Some(Box::new(black_knight)), black_knight_pos);
 
         let actual_legal_moves = game.legal_moves();
-        let expected_legal_moves = vec![
+        let expected_legal_moves = [
             chessmove::ChessMove {
                 from: (3, 2),
                 to: (4, 3),
@@ -1394,11 +2058,28 @@ mod tests {
         assert!(game.board.get_square(position::Position(5, 2)).is_some());
         assert!(game.board.get_square(position::Position(5, 4)).is_none());
         assert_eq!(color::Color::WHITE, game.side_to_move);
-        if let Some(_) = game.en_passant() {
+        if game.en_passant().is_some() {
             panic!();
         }
     }
 
+    #[test]
+    fn undo_restores_castling_en_passant_and_promotion() {
+        for (fen, mv) in &[
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "O-O"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "O-O-O"),
+            ("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 2", "dxe6"),
+            ("1n2k3/P7/8/8/8/8/8/4K3 w - - 4 30", "axb8=Q"),
+        ] {
+            let mut game = Game::from_fen(fen).unwrap();
+            let mv = game.parse_san(mv).unwrap();
+            game.make_move(mv);
+            game.undo_last_move();
+            assert_eq!(*fen, game.to_fen());
+            assert_eq!(Game::from_fen(fen).unwrap().board, game.board);
+        }
+    }
+
     #[test]
     fn castling_kingside_leads_to_pieces_having_moved() {
         let mut game = Game::new();
@@ -1559,6 +2240,508 @@ mod tests {
         }));
     }
 
+    const HASH_OF_INITIAL_POSITION: u64 = 17289618357511384596;
+
+    #[test]
+    fn half_moves_reset_on_pawn_moves_and_captures() {
+        let mut game = Game::new();
+        play(&mut game, &["Nf3", "Nc6", "Ng1"]);
+        assert_eq!(3, game.half_moves());
+
+        play(&mut game, &["e5"]);
+        assert_eq!(0, game.half_moves());
+
+        play(&mut game, &["Nf3", "Nb4", "Nxe5"]);
+        assert_eq!(0, game.half_moves());
+
+        game.undo_last_move();
+        assert_eq!(2, game.half_moves());
+        assert_eq!(2, game.to_game_arr()[70]);
+    }
+
+    #[test]
+    fn fifty_move_claim_after_one_hundred_plies() {
+        let mut game = Game::from_fen("8/8/4k3/8/8/3K4/8/7R w - - 97 80").unwrap();
+        play(&mut game, &["Re1", "Kd6"]);
+        assert!(!game.can_claim_fifty_move_draw());
+        assert_eq!(None, game.claim_draw());
+
+        play(&mut game, &["Re2"]);
+        assert_eq!(100, game.half_moves());
+        assert!(game.can_claim_fifty_move_draw());
+        assert_eq!(None, game.outcome());
+        assert_eq!(
+            Some(outcome::Outcome::Draw {
+                reason: outcome::Reason::FiftyMoveRule
+            }),
+            game.claim_draw()
+        );
+
+        game.undo_last_move();
+        assert!(!game.can_claim_fifty_move_draw());
+    }
+
+    #[test]
+    fn seventy_five_move_draw_after_one_hundred_fifty_plies() {
+        let mut game = Game::from_fen("8/8/4k3/8/8/3K4/8/7R w - - 147 105").unwrap();
+        play(&mut game, &["Re1", "Kd6"]);
+        assert!(!game.is_seventy_five_move_draw());
+        assert_eq!(None, game.outcome());
+
+        play(&mut game, &["Re2"]);
+        assert!(game.is_seventy_five_move_draw());
+        assert_eq!(
+            Some(outcome::Outcome::Draw {
+                reason: outcome::Reason::SeventyFiveMoveRule
+            }),
+            game.outcome()
+        );
+    }
+
+    #[test]
+    fn mate_on_the_one_hundred_fiftieth_ply_is_still_mate() {
+        let mut game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 149 110").unwrap();
+        play(&mut game, &["Ra8#"]);
+        assert!(game.is_seventy_five_move_draw());
+        assert_eq!(
+            Some(outcome::Outcome::Win {
+                winner: color::Color::WHITE,
+                reason: outcome::Reason::Checkmate,
+            }),
+            game.outcome()
+        );
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut game = Game::new();
+        let knight_dance = ["Nf3", "Nf6", "Ng1", "Ng8"];
+        play(&mut game, &knight_dance);
+        assert_eq!(2, game.repetition_count());
+        assert!(!game.can_claim_threefold());
+
+        play(&mut game, &knight_dance);
+        assert_eq!(3, game.repetition_count());
+        assert!(game.can_claim_threefold());
+        assert!(!game.is_fivefold_repetition());
+
+        game.undo_last_move();
+        assert_eq!(2, game.repetition_count());
+    }
+
+    #[test]
+    fn castling_rights_make_positions_differ() {
+        let mut game = Game::new();
+        play(&mut game, &["e4", "e5", "Ke2", "Ke7", "Ke1", "Ke8"]);
+        assert_eq!(1, game.repetition_count());
+
+        play(&mut game, &["Ke2", "Ke7", "Ke1", "Ke8"]);
+        assert_eq!(2, game.repetition_count());
+    }
+
+    #[test]
+    fn en_passant_only_counts_if_the_capture_is_legal() {
+        // nothing can take on e3 after 1. e4, so the position repeats after 3. Ng1
+        let mut game = Game::new();
+        play(&mut game, &["e4", "Nf6", "Nf3", "Ng8", "Ng1"]);
+        assert_eq!(2, game.repetition_count());
+
+        // exd6 is possible after 2... d5 but not after 4... Nc6
+        let mut game = Game::new();
+        play(&mut game, &["e4", "Nc6", "e5", "d5"]);
+        play(&mut game, &["Nf3", "Nb8", "Ng1", "Nc6"]);
+        assert_eq!(1, game.repetition_count());
+        play(&mut game, &["Nf3", "Nb8", "Ng1", "Nc6"]);
+        assert_eq!(2, game.repetition_count());
+    }
+
+    #[test]
+    fn fivefold_repetition() {
+        let mut game = Game::new();
+        let knight_dance = ["Nc3", "Nc6", "Nb1", "Nb8"];
+        for _ in 0..3 {
+            play(&mut game, &knight_dance);
+        }
+        assert_eq!(4, game.repetition_count());
+        assert!(!game.is_fivefold_repetition());
+
+        play(&mut game, &knight_dance);
+        assert!(game.is_fivefold_repetition());
+        assert_eq!(
+            Some(outcome::Outcome::Draw {
+                reason: outcome::Reason::FivefoldRepetition
+            }),
+            game.outcome()
+        );
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let dead = [
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KN3/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KB3/8/8 w - - 0 1",
+            "8/8/3bk3/8/8/3KB3/8/8 w - - 0 1",
+            "8/8/1b2k3/8/8/3KB3/8/2B5 w - - 0 1",
+        ];
+        for fen in &dead {
+            assert!(
+                Game::from_fen(fen).unwrap().is_insufficient_material(),
+                "{}",
+                fen
+            );
+        }
+
+        let alive = [
+            "8/8/4k3/8/8/3KP3/8/8 w - - 0 1",
+            "8/8/2b1k3/8/8/3KB3/8/8 w - - 0 1",
+            "8/8/2n1k3/8/8/3KN3/8/8 w - - 0 1",
+            "8/8/2n1k3/8/8/3KB3/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KBB2/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KNN2/8/8 w - - 0 1",
+        ];
+        for fen in &alive {
+            assert!(
+                !Game::from_fen(fen).unwrap().is_insufficient_material(),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn mating_material_per_color() {
+        // after a flag fall, the side with only a knight cannot win against a bare king
+        let game = Game::from_fen("8/8/4k3/8/8/3KN3/8/8 w - - 0 1").unwrap();
+        assert!(!game.has_mating_material(color::Color::WHITE));
+        assert!(!game.has_mating_material(color::Color::BLACK));
+
+        // but it can if the other king may be boxed in by its own pawn
+        let game = Game::from_fen("8/4p3/4k3/8/8/3KN3/8/8 w - - 0 1").unwrap();
+        assert!(game.has_mating_material(color::Color::WHITE));
+        assert!(game.has_mating_material(color::Color::BLACK));
+
+        // bishops on one colour only can never deliver mate
+        let game = Game::from_fen("8/8/3bk3/8/8/3KB3/8/8 w - - 0 1").unwrap();
+        assert!(!game.has_mating_material(color::Color::WHITE));
+        assert!(!game.has_mating_material(color::Color::BLACK));
+
+        let game = Game::from_fen("8/8/2b1k3/8/8/3KB3/8/8 w - - 0 1").unwrap();
+        assert!(game.has_mating_material(color::Color::WHITE));
+        assert!(game.has_mating_material(color::Color::BLACK));
+
+        let game = Game::from_fen("8/8/4k3/8/8/3K4/8/7R w - - 0 1").unwrap();
+        assert!(game.has_mating_material(color::Color::WHITE));
+        assert!(!game.has_mating_material(color::Color::BLACK));
+    }
+
+    #[test]
+    fn try_make_move_rejects_illegal_moves() {
+        let mut game = Game::new();
+        let mv = chessmove::ChessMove {
+            from: (5, 2),
+            to: (5, 5),
+            promotion: None,
+        };
+        assert_eq!(Err(error::Error::IllegalMove(mv)), game.try_make_move(mv));
+        assert_eq!(INITIAL_GAME_ARR, game.to_game_arr());
+
+        let mv = chessmove::ChessMove {
+            from: (5, 2),
+            to: (5, 4),
+            promotion: None,
+        };
+        assert_eq!(Ok(()), game.try_make_move(mv));
+        assert_eq!(Ok(()), game.try_undo_last_move());
+        assert_eq!(Err(error::Error::NoHistory), game.try_undo_last_move());
+    }
+
+    #[test]
+    fn try_from_game_arr() {
+        assert!(Game::try_from_game_arr(&INITIAL_GAME_ARR).is_ok());
+        assert_eq!(
+            Some(error::Error::MalformedGameArray),
+            Game::try_from_game_arr(&INITIAL_GAME_ARR[..72]).err()
+        );
+        assert_eq!(
+            Some(error::Error::MalformedGameArray),
+            Game::try_from_game_arr(&[0; 10]).err()
+        );
+
+        let mut game_arr = INITIAL_GAME_ARR;
+        game_arr[64] = 9;
+        game_arr[65] = 3;
+        assert_eq!(
+            Some(error::Error::MalformedGameArray),
+            Game::try_from_game_arr(&game_arr).err()
+        );
+
+        let mut game_arr = INITIAL_GAME_ARR;
+        game_arr[32] = 0;
+        assert_eq!(
+            Some(error::Error::MissingKing(color::Color::WHITE)),
+            Game::try_from_game_arr(&game_arr).err()
+        );
+
+        let mut game_arr = INITIAL_GAME_ARR;
+        game_arr[20] = 42;
+        assert_eq!(
+            Some(error::Error::InvalidPieceCode(42)),
+            Game::try_from_game_arr(&game_arr).err()
+        );
+    }
+
+    #[test]
+    fn try_from_game_arr_checks_castling_and_en_passant() {
+        // only the kings on e1 and e8, with white allowed to castle kingside
+        let mut game_arr = [0; 73];
+        game_arr[32] = 6;
+        game_arr[39] = 16;
+        game_arr[66] = 1;
+        assert_eq!(
+            Some(error::Error::MalformedGameArray),
+            Game::try_from_game_arr(&game_arr).err()
+        );
+        game_arr[66] = 0;
+        assert!(Game::try_from_game_arr(&game_arr).is_ok());
+
+        // the h8 rook is gone but black may still castle kingside
+        let mut game_arr = INITIAL_GAME_ARR;
+        game_arr[63] = 0;
+        assert_eq!(
+            Some(error::Error::MalformedGameArray),
+            Game::try_from_game_arr(&game_arr).err()
+        );
+
+        let mut game = Game::new();
+        game.make_move(chessmove::ChessMove {
+            from: (5, 2),
+            to: (5, 4),
+            promotion: None,
+        });
+        let game_arr = game.to_game_arr();
+        assert!(Game::try_from_game_arr(&game_arr).is_ok());
+
+        // e3 with white to move, and e6 which has no pawn in front of it
+        let mut wrong_side = game_arr;
+        wrong_side[72] = 0;
+        let mut no_pawn = game_arr;
+        no_pawn[65] = 6;
+        no_pawn[72] = 0;
+        for game_arr in &[wrong_side, no_pawn] {
+            assert_eq!(
+                Some(error::Error::MalformedGameArray),
+                Game::try_from_game_arr(game_arr).err()
+            );
+        }
+    }
+
+    // compares the incremental hash with a fresh one at every node, and after taking moves back
+    fn check_hash(game: &mut Game, depth: u8) {
+        assert_eq!(game.recompute_hash(), game.hash(), "{}", game.to_fen());
+        if depth == 0 {
+            return;
+        }
+        let hash = game.hash();
+        for mv in game.legal_moves() {
+            game.make_move(mv);
+            check_hash(game, depth - 1);
+            game.undo_last_move();
+            assert_eq!(hash, game.hash());
+        }
+    }
+
+    #[test]
+    fn incremental_hash_matches_recomputed_hash() {
+        for fen in &[
+            fen::INITIAL_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            check_hash(&mut Game::from_fen(fen).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        assert_eq!(HASH_OF_INITIAL_POSITION, Game::new().hash());
+        assert_eq!(
+            Game::new().hash(),
+            Game::from_fen(fen::INITIAL_FEN).unwrap().hash()
+        );
+    }
+
+    #[test]
+    fn checkers() {
+        let game = Game::new();
+        assert!(!game.is_check());
+        assert!(game.checkers().is_empty());
+
+        let game = Game::from_fen("4k3/8/8/8/1b6/8/8/4K3 w - - 0 1").unwrap();
+        assert!(game.is_check());
+        assert!(!game.is_double_check());
+        assert_eq!(
+            vec!["b4".parse::<square::Square>().unwrap()],
+            game.checkers()
+        );
+
+        let game = Game::from_fen("4k3/8/8/8/8/5n2/8/r3K3 w - - 0 1").unwrap();
+        assert!(game.is_double_check());
+        let checkers = game.checkers();
+        assert_eq!(2, checkers.len());
+        assert!(checkers.contains(&"f3".parse().unwrap()));
+        assert!(checkers.contains(&"a1".parse().unwrap()));
+    }
+
+    #[test]
+    fn gives_check() {
+        for (fen, checks, quiet) in &[
+            // direct
+            (fen::INITIAL_FEN, &[][..], &["e4", "Nf3"][..]),
+            (
+                "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+                &["Qd7+", "Qa4+"],
+                &["Qd6"],
+            ),
+            // discovered
+            (
+                "4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1",
+                &["Nc3+", "Nd4+"],
+                &["Kf2"],
+            ),
+            // castling rook
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", &["O-O+"], &["Kd1"]),
+            // en passant removes both pawns from the rank
+            ("8/8/8/k1pP3R/8/8/8/4K3 w - c6 0 1", &["dxc6+"], &["d6"]),
+            // promotion
+            (
+                "3k4/1P6/8/8/8/8/8/4K3 w - - 0 1",
+                &["b8=Q+", "b8=R+"],
+                &["b8=N", "b8=B"],
+            ),
+        ] {
+            let game = Game::from_fen(fen).unwrap();
+            for san in checks.iter() {
+                assert!(game.gives_check(&game.parse_san(san).unwrap()), "{}", san);
+            }
+            for san in quiet.iter() {
+                assert!(!game.gives_check(&game.parse_san(san).unwrap()), "{}", san);
+            }
+        }
+
+        // nothing stands on d4
+        assert!(!Game::new().gives_check(&"d4d5".parse().unwrap()));
+        assert!(!Game::new().gives_check(&chessmove::ChessMove {
+            from: (0, 0),
+            to: (9, 9),
+            promotion: None,
+        }));
+    }
+
+    fn check_gives_check(game: &mut Game, depth: u8) {
+        for mv in game.legal_moves() {
+            let gives_check = game.gives_check(&mv);
+            game.make_move(mv);
+            assert_eq!(game.is_check(), gives_check, "{} {}", mv, game.to_fen());
+            if depth > 1 {
+                check_gives_check(game, depth - 1);
+            }
+            game.undo_last_move();
+        }
+    }
+
+    #[test]
+    fn gives_check_matches_making_the_move() {
+        for fen in &[
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            check_gives_check(&mut Game::from_fen(fen).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn legal_moves_detailed() {
+        let game =
+            Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1")
+                .unwrap();
+        let moves = game.legal_moves_detailed();
+        assert_eq!(
+            game.legal_moves(),
+            moves.iter().map(|info| info.chessmove).collect::<Vec<_>>()
+        );
+
+        let info = |uci: &str| {
+            let mv = game.parse_uci_move(uci).unwrap();
+            *moves.iter().find(|info| info.chessmove == mv).unwrap()
+        };
+
+        let castle = info("e8g8");
+        assert_eq!(chessmove::MoveKind::CastleKingside, castle.kind);
+        assert!(castle.is_castle() && !castle.is_capture());
+        assert_eq!(chessmove::MoveKind::CastleQueenside, info("e8c8").kind);
+
+        let en_passant = info("b4a3");
+        assert_eq!(chessmove::MoveKind::EnPassant, en_passant.kind);
+        assert_eq!(
+            Some((piece::PieceEnum::PAWN, "a4".parse().unwrap())),
+            en_passant.captured
+        );
+
+        let capture = info("h3g2");
+        assert_eq!(piece::PieceEnum::PAWN, capture.piece);
+        assert_eq!(chessmove::MoveKind::Normal, capture.kind);
+        assert_eq!(
+            Some((piece::PieceEnum::PAWN, "g2".parse().unwrap())),
+            capture.captured
+        );
+        assert_eq!(
+            Some((piece::PieceEnum::BISHOP, "e2".parse().unwrap())),
+            info("a6e2").captured
+        );
+
+        assert_eq!(chessmove::MoveKind::DoublePawnPush, info("c7c5").kind);
+        assert_eq!(chessmove::MoveKind::Normal, info("c7c6").kind);
+        assert_eq!(None, info("c7c6").captured);
+
+        let game = Game::from_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotions = game
+            .legal_moves_detailed()
+            .into_iter()
+            .filter(|info| info.kind == chessmove::MoveKind::Promotion)
+            .collect::<Vec<_>>();
+        assert_eq!(8, promotions.len());
+        assert_eq!(
+            4,
+            promotions.iter().filter(|info| info.is_capture()).count()
+        );
+    }
+
+    #[test]
+    fn transpositions_share_a_hash() {
+        let mut a = Game::new();
+        play(&mut a, &["Nf3", "Nf6", "Nc3"]);
+        let mut b = Game::new();
+        play(&mut b, &["Nc3", "Nf6", "Nf3"]);
+        assert_eq!(a.hash(), b.hash());
+
+        // same placement, different side to move, castling rights or en passant file
+        let mut c = Game::new();
+        play(&mut c, &["e4"]);
+        let mut d = Game::new();
+        play(&mut d, &["e3", "Nf6", "e4", "Ng8"]);
+        assert_ne!(c.hash(), d.hash());
+        let mut e = Game::new();
+        play(
+            &mut e,
+            &["Nf3", "Nf6", "Rg1", "Ng8", "Rh1", "Nf6", "Ng1", "Ng8"],
+        );
+        assert_ne!(Game::new().hash(), e.hash());
+    }
+
     fn set_piece(board: &mut board::Board, piece: Box<dyn piece::Piece>) {
         let position = *piece.position();
         board.set_square(Some(piece), position);
@@ -1,6 +1,21 @@
 pub mod attack;
+#[cfg(feature = "bitboard")]
+pub mod bitboard;
 pub mod board;
+pub mod book;
 pub mod chessmove;
 pub mod color;
+pub mod display;
+pub mod encoding;
+pub mod error;
+pub mod fen;
 pub mod game;
+pub mod outcome;
+pub mod pgn;
 pub mod pieces;
+pub mod san;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod square;
+pub mod svg;
+mod zobrist;
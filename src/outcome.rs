@@ -0,0 +1,191 @@
+use crate::color;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Reason {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    // only drawn once a player claims it
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    // only drawn once a player claims it
+    ThreefoldRepetition,
+    FivefoldRepetition,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Outcome {
+    Win {
+        winner: color::Color,
+        reason: Reason,
+    },
+    Draw {
+        reason: Reason,
+    },
+}
+
+impl Outcome {
+    pub fn winner(&self) -> Option<color::Color> {
+        match self {
+            Outcome::Win { winner, .. } => Some(*winner),
+            Outcome::Draw { .. } => None,
+        }
+    }
+
+    pub fn reason(&self) -> Reason {
+        match self {
+            Outcome::Win { reason, .. } | Outcome::Draw { reason } => *reason,
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Reason::Checkmate => "checkmate",
+            Reason::Stalemate => "stalemate",
+            Reason::InsufficientMaterial => "insufficient material",
+            Reason::FiftyMoveRule => "fifty-move rule",
+            Reason::SeventyFiveMoveRule => "seventy-five-move rule",
+            Reason::ThreefoldRepetition => "threefold repetition",
+            Reason::FivefoldRepetition => "fivefold repetition",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Win {
+                winner: color::Color::WHITE,
+                reason,
+            } => write!(f, "white wins by {}", reason),
+            Outcome::Win {
+                winner: color::Color::BLACK,
+                reason,
+            } => write!(f, "black wins by {}", reason),
+            Outcome::Draw { reason } => write!(f, "draw by {}", reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game;
+
+    #[test]
+    fn ongoing_game_has_no_outcome() {
+        let mut game = game::Game::new();
+        assert_eq!(None, game.outcome());
+        game::play(&mut game, &["e4", "e5", "Qh5", "Nc6", "Bc4"]);
+        assert_eq!(None, game.outcome());
+    }
+
+    #[test]
+    fn checkmate() {
+        let mut game = game::Game::new();
+        game::play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+        let outcome = game.outcome().unwrap();
+        assert_eq!(
+            Outcome::Win {
+                winner: color::Color::BLACK,
+                reason: Reason::Checkmate,
+            },
+            outcome
+        );
+        assert_eq!(Some(color::Color::BLACK), outcome.winner());
+        assert_eq!("black wins by checkmate", outcome.to_string());
+    }
+
+    #[test]
+    fn stalemate() {
+        let game = game::Game::from_fen("7k/5Q2/8/8/8/8/8/K7 b - - 0 1").unwrap();
+        assert_eq!(
+            Some(Outcome::Draw {
+                reason: Reason::Stalemate
+            }),
+            game.outcome()
+        );
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for fen in &[
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KN3/8/8 b - - 0 1",
+        ] {
+            let game = game::Game::from_fen(fen).unwrap();
+            assert_eq!(
+                Some(Outcome::Draw {
+                    reason: Reason::InsufficientMaterial
+                }),
+                game.outcome()
+            );
+        }
+
+        let game = game::Game::from_fen("8/8/4k3/8/8/3KR3/8/8 b - - 0 1").unwrap();
+        assert_eq!(None, game.outcome());
+    }
+
+    #[test]
+    fn seventy_five_move_rule_is_automatic() {
+        let game = game::Game::from_fen("8/8/4k3/8/8/3KR3/8/8 b - - 150 120").unwrap();
+        assert_eq!(
+            Some(Outcome::Draw {
+                reason: Reason::SeventyFiveMoveRule
+            }),
+            game.outcome()
+        );
+    }
+
+    #[test]
+    fn checkmate_beats_seventy_five_move_rule() {
+        let game = game::Game::from_fen("4R1k1/5ppp/8/8/8/8/8/6K1 b - - 150 120").unwrap();
+        assert_eq!(
+            Some(Outcome::Win {
+                winner: color::Color::WHITE,
+                reason: Reason::Checkmate,
+            }),
+            game.outcome()
+        );
+    }
+
+    #[test]
+    fn fifty_move_rule_must_be_claimed() {
+        let game = game::Game::from_fen("8/8/4k3/8/8/3KR3/8/8 b - - 100 80").unwrap();
+        assert_eq!(None, game.outcome());
+        assert_eq!(
+            Some(Outcome::Draw {
+                reason: Reason::FiftyMoveRule
+            }),
+            game.claim_draw()
+        );
+    }
+
+    #[test]
+    fn repetition() {
+        let mut game = game::Game::new();
+        let knight_dance = ["Nf3", "Nf6", "Ng1", "Ng8"];
+        game::play(&mut game, &knight_dance);
+        assert_eq!(None, game.claim_draw());
+        game::play(&mut game, &knight_dance);
+        assert_eq!(None, game.outcome());
+        assert_eq!(
+            Some(Outcome::Draw {
+                reason: Reason::ThreefoldRepetition
+            }),
+            game.claim_draw()
+        );
+        game::play(&mut game, &knight_dance);
+        game::play(&mut game, &knight_dance);
+        assert_eq!(
+            Some(Outcome::Draw {
+                reason: Reason::FivefoldRepetition
+            }),
+            game.outcome()
+        );
+    }
+}
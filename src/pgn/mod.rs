@@ -0,0 +1,78 @@
+pub mod reader;
+pub mod writer;
+
+use crate::{chessmove, fen, game};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Undecided,
+}
+
+impl GameResult {
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Undecided),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let token = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Undecided => "*",
+        };
+        write!(f, "{}", token)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PgnMove {
+    pub san: String,
+    pub chessmove: chessmove::ChessMove,
+    pub nags: Vec<u8>,
+    // comments in front of the first move of a variation
+    pub pre_comments: Vec<String>,
+    pub comments: Vec<String>,
+    // alternatives to this move, each starting from the position before it
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    // comments in front of the first move of the mainline
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+    pub result: Option<GameResult>,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn starting_position(&self) -> Result<game::Game, fen::FenError> {
+        match self.tag("FEN") {
+            None => Ok(game::Game::new()),
+            Some(fen) => game::Game::from_fen(fen),
+        }
+    }
+
+    pub fn mainline(&self) -> Vec<chessmove::ChessMove> {
+        self.moves.iter().map(|mv| mv.chessmove).collect()
+    }
+}
@@ -0,0 +1,733 @@
+use super::{GameResult, PgnGame, PgnMove};
+use crate::{fen, game, san};
+use std::io::BufRead;
+use std::{error, fmt};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PgnErrorKind {
+    Io(String),
+    UnexpectedToken(String),
+    UnexpectedEndOfFile,
+    UnterminatedComment,
+    UnterminatedString,
+    InvalidTag,
+    InvalidFen(fen::FenError),
+    InvalidMove(san::SanError),
+    AnnotationWithoutMove,
+    VariationWithoutMove,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PgnError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PgnErrorKind,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            PgnErrorKind::Io(e) => write!(f, "{}", e),
+            PgnErrorKind::UnexpectedToken(token) => write!(f, "unexpected '{}'", token),
+            PgnErrorKind::UnexpectedEndOfFile => write!(f, "unexpected end of file"),
+            PgnErrorKind::UnterminatedComment => write!(f, "unterminated comment"),
+            PgnErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            PgnErrorKind::InvalidTag => write!(f, "invalid tag pair"),
+            PgnErrorKind::InvalidFen(e) => write!(f, "{}", e),
+            PgnErrorKind::InvalidMove(e) => write!(f, "{}", e),
+            PgnErrorKind::AnnotationWithoutMove => write!(f, "annotation without a move"),
+            PgnErrorKind::VariationWithoutMove => write!(f, "variation without a move"),
+        }
+    }
+}
+
+impl error::Error for PgnError {}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    TagOpen,
+    TagClose,
+    Str(String),
+    Symbol(String),
+    Suffix(String),
+    Comment(String),
+    Nag(u8),
+    VariationOpen,
+    VariationClose,
+    Result(GameResult),
+    Eof,
+}
+
+// Reads one game at a time. After an error the reader skips ahead to the next tag section.
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    line: Vec<char>,
+    line_number: usize,
+    column: usize,
+    eof: bool,
+    peeked: Option<(Token, usize, usize)>,
+    recovering: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: vec![],
+            line_number: 0,
+            column: 0,
+            eof: false,
+            peeked: None,
+            recovering: false,
+        }
+    }
+
+    pub fn read_game(&mut self) -> Result<Option<PgnGame>, PgnError> {
+        if self.recovering {
+            self.recovering = false;
+            self.skip_to_next_game()?;
+        }
+
+        let result = self.parse_game();
+        if result.is_err() {
+            self.recovering = true;
+        }
+        result
+    }
+
+    fn parse_game(&mut self) -> Result<Option<PgnGame>, PgnError> {
+        let mut pgn_game = PgnGame::default();
+
+        if self.peek_token()?.0 == Token::Eof {
+            return Ok(None);
+        }
+
+        let mut fen_location = None;
+        while self.peek_token()?.0 == Token::TagOpen {
+            self.next_token()?;
+            let (name, line, column) = self.next_token()?;
+            let name = match name {
+                Token::Symbol(name) => name,
+                _ => return Err(self.error_at(line, column, PgnErrorKind::InvalidTag)),
+            };
+            let value = match self.next_token()? {
+                (Token::Str(value), _, _) => value,
+                (_, line, column) => {
+                    return Err(self.error_at(line, column, PgnErrorKind::InvalidTag))
+                }
+            };
+            match self.next_token()? {
+                (Token::TagClose, _, _) => (),
+                (_, line, column) => {
+                    return Err(self.error_at(line, column, PgnErrorKind::InvalidTag))
+                }
+            }
+            if name == "FEN" {
+                fen_location = Some((line, column));
+            }
+            pgn_game.tags.push((name, value));
+        }
+
+        let mut game = match pgn_game.starting_position() {
+            Ok(game) => game,
+            Err(e) => {
+                let (line, column) = fen_location.unwrap_or((self.line_number, 1));
+                return Err(self.error_at(line, column, PgnErrorKind::InvalidFen(e)));
+            }
+        };
+
+        let mut moves = vec![];
+        let result = self.parse_line(&mut game, &mut moves, &mut pgn_game.comments, false)?;
+        pgn_game.moves = moves;
+        pgn_game.result = result;
+
+        Ok(Some(pgn_game))
+    }
+
+    fn parse_line(
+        &mut self,
+        game: &mut game::Game,
+        moves: &mut Vec<PgnMove>,
+        leading_comments: &mut Vec<String>,
+        in_variation: bool,
+    ) -> Result<Option<GameResult>, PgnError> {
+        loop {
+            let (token, line, column) = self.peek_token()?;
+            match token {
+                Token::TagOpen | Token::Eof if !in_variation => return Ok(None),
+                Token::TagOpen => {
+                    return Err(self.error_at(
+                        line,
+                        column,
+                        PgnErrorKind::UnexpectedToken("[".into()),
+                    ))
+                }
+                Token::Eof => {
+                    return Err(self.error_at(line, column, PgnErrorKind::UnexpectedEndOfFile))
+                }
+                _ => (),
+            }
+            self.next_token()?;
+
+            match token {
+                Token::Comment(comment) => match moves.last_mut() {
+                    None => leading_comments.push(comment),
+                    Some(mv) => mv.comments.push(comment),
+                },
+                Token::Nag(nag) => match moves.last_mut() {
+                    None => {
+                        return Err(self.error_at(
+                            line,
+                            column,
+                            PgnErrorKind::AnnotationWithoutMove,
+                        ))
+                    }
+                    Some(mv) => mv.nags.push(nag),
+                },
+                Token::Suffix(suffix) => {
+                    let nag = match suffix.as_str() {
+                        "!" => 1,
+                        "?" => 2,
+                        "!!" => 3,
+                        "??" => 4,
+                        "!?" => 5,
+                        "?!" => 6,
+                        _ => {
+                            return Err(self.error_at(
+                                line,
+                                column,
+                                PgnErrorKind::UnexpectedToken(suffix),
+                            ))
+                        }
+                    };
+                    match moves.last_mut() {
+                        None => {
+                            return Err(self.error_at(
+                                line,
+                                column,
+                                PgnErrorKind::AnnotationWithoutMove,
+                            ))
+                        }
+                        Some(mv) => mv.nags.push(nag),
+                    }
+                }
+                Token::Symbol(symbol) => {
+                    if symbol.chars().all(|c| c.is_ascii_digit()) {
+                        // move number, the periods following it are skipped by the tokenizer
+                        continue;
+                    }
+                    let chessmove = game
+                        .parse_san(&symbol)
+                        .map_err(|e| self.error_at(line, column, PgnErrorKind::InvalidMove(e)))?;
+                    game.make_move(chessmove);
+                    let pre_comments = if moves.is_empty() && in_variation {
+                        std::mem::take(leading_comments)
+                    } else {
+                        vec![]
+                    };
+                    moves.push(PgnMove {
+                        san: symbol,
+                        chessmove,
+                        nags: vec![],
+                        pre_comments,
+                        comments: vec![],
+                        variations: vec![],
+                    });
+                }
+                Token::VariationOpen => {
+                    let last = match moves.last() {
+                        None => {
+                            return Err(self.error_at(
+                                line,
+                                column,
+                                PgnErrorKind::VariationWithoutMove,
+                            ))
+                        }
+                        Some(mv) => mv.chessmove,
+                    };
+                    game.undo_last_move();
+
+                    let mut variation = vec![];
+                    let mut variation_comments = vec![];
+                    self.parse_line(game, &mut variation, &mut variation_comments, true)?;
+                    for _ in 0..variation.len() {
+                        game.undo_last_move();
+                    }
+                    game.make_move(last);
+
+                    if let Some(mv) = moves.last_mut() {
+                        if variation.is_empty() {
+                            mv.comments.append(&mut variation_comments);
+                        } else {
+                            mv.variations.push(variation);
+                        }
+                    }
+                }
+                Token::VariationClose if in_variation => return Ok(None),
+                Token::Result(result) if !in_variation => return Ok(Some(result)),
+                Token::VariationClose => {
+                    return Err(self.error_at(
+                        line,
+                        column,
+                        PgnErrorKind::UnexpectedToken(")".into()),
+                    ))
+                }
+                Token::Result(result) => {
+                    return Err(self.error_at(
+                        line,
+                        column,
+                        PgnErrorKind::UnexpectedToken(result.to_string()),
+                    ))
+                }
+                Token::TagClose => {
+                    return Err(self.error_at(
+                        line,
+                        column,
+                        PgnErrorKind::UnexpectedToken("]".into()),
+                    ))
+                }
+                Token::Str(_) => {
+                    return Err(self.error_at(
+                        line,
+                        column,
+                        PgnErrorKind::UnexpectedToken("\"".into()),
+                    ))
+                }
+                Token::TagOpen | Token::Eof => unreachable!(),
+            }
+        }
+    }
+
+    fn skip_to_next_game(&mut self) -> Result<(), PgnError> {
+        self.peeked = None;
+        let mut previous_was_tag = self.line.first() == Some(&'[');
+        loop {
+            self.column = self.line.len();
+            if !self.read_line()? {
+                return Ok(());
+            }
+            let is_tag = self.line.first() == Some(&'[');
+            if is_tag && !previous_was_tag {
+                return Ok(());
+            }
+            previous_was_tag = is_tag;
+        }
+    }
+
+    fn error_at(&self, line: usize, column: usize, kind: PgnErrorKind) -> PgnError {
+        PgnError { line, column, kind }
+    }
+
+    fn error(&self, kind: PgnErrorKind) -> PgnError {
+        self.error_at(self.line_number, self.column + 1, kind)
+    }
+
+    fn read_line(&mut self) -> Result<bool, PgnError> {
+        loop {
+            let mut buf = String::new();
+            let read = self
+                .reader
+                .read_line(&mut buf)
+                .map_err(|e| self.error(PgnErrorKind::Io(e.to_string())))?;
+            if read == 0 {
+                self.eof = true;
+                return Ok(false);
+            }
+            self.line_number += 1;
+            self.column = 0;
+            self.line = buf.trim_start_matches('\u{feff}').chars().collect();
+            // lines starting with '%' are escaped and ignored
+            if self.line.first() != Some(&'%') {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>, PgnError> {
+        while self.column >= self.line.len() {
+            if self.eof || !self.read_line()? {
+                return Ok(None);
+            }
+        }
+        Ok(Some(self.line[self.column]))
+    }
+
+    fn peek_token(&mut self) -> Result<(Token, usize, usize), PgnError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read_token()?);
+        }
+        match &self.peeked {
+            Some(peeked) => Ok(peeked.clone()),
+            None => unreachable!(),
+        }
+    }
+
+    fn next_token(&mut self) -> Result<(Token, usize, usize), PgnError> {
+        match self.peeked.take() {
+            Some(peeked) => Ok(peeked),
+            None => self.read_token(),
+        }
+    }
+
+    fn read_token(&mut self) -> Result<(Token, usize, usize), PgnError> {
+        loop {
+            let c = match self.peek_char()? {
+                None => return Ok((Token::Eof, self.line_number, self.column + 1)),
+                Some(c) => c,
+            };
+            let (line, column) = (self.line_number, self.column + 1);
+
+            if c.is_whitespace() || c == '.' {
+                self.column += 1;
+                continue;
+            }
+
+            let token = match c {
+                '[' => {
+                    self.column += 1;
+                    Token::TagOpen
+                }
+                ']' => {
+                    self.column += 1;
+                    Token::TagClose
+                }
+                '(' => {
+                    self.column += 1;
+                    Token::VariationOpen
+                }
+                ')' => {
+                    self.column += 1;
+                    Token::VariationClose
+                }
+                '*' => {
+                    self.column += 1;
+                    Token::Result(GameResult::Undecided)
+                }
+                ';' => {
+                    let comment = self.line[self.column + 1..]
+                        .iter()
+                        .collect::<String>()
+                        .trim()
+                        .to_string();
+                    self.column = self.line.len();
+                    Token::Comment(comment)
+                }
+                '{' => {
+                    self.column += 1;
+                    let mut comment = String::new();
+                    loop {
+                        match self.peek_char()? {
+                            None => {
+                                return Err(self.error_at(
+                                    line,
+                                    column,
+                                    PgnErrorKind::UnterminatedComment,
+                                ))
+                            }
+                            Some('}') => {
+                                self.column += 1;
+                                break;
+                            }
+                            Some(c) => {
+                                comment.push(c);
+                                self.column += 1;
+                            }
+                        }
+                    }
+                    Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" "))
+                }
+                '"' => {
+                    self.column += 1;
+                    let mut value = String::new();
+                    loop {
+                        // strings cannot span lines
+                        if self.column >= self.line.len() {
+                            return Err(self.error_at(
+                                line,
+                                column,
+                                PgnErrorKind::UnterminatedString,
+                            ));
+                        }
+                        let c = self.line[self.column];
+                        self.column += 1;
+                        match c {
+                            '"' => break,
+                            '\\' if self.column < self.line.len() => {
+                                value.push(self.line[self.column]);
+                                self.column += 1;
+                            }
+                            '\n' | '\r' => {
+                                return Err(self.error_at(
+                                    line,
+                                    column,
+                                    PgnErrorKind::UnterminatedString,
+                                ))
+                            }
+                            c => value.push(c),
+                        }
+                    }
+                    Token::Str(value)
+                }
+                '$' => {
+                    self.column += 1;
+                    let digits = self.take_while(|c| c.is_ascii_digit());
+                    match digits.parse::<u8>() {
+                        Ok(nag) => Token::Nag(nag),
+                        Err(_) => {
+                            return Err(self.error_at(
+                                line,
+                                column,
+                                PgnErrorKind::UnexpectedToken(format!("${}", digits)),
+                            ))
+                        }
+                    }
+                }
+                '!' | '?' => Token::Suffix(self.take_while(|c| c == '!' || c == '?')),
+                c if c.is_ascii_alphanumeric() => {
+                    let symbol =
+                        self.take_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(c));
+                    match GameResult::from_token(&symbol) {
+                        Some(result) => Token::Result(result),
+                        None => Token::Symbol(symbol),
+                    }
+                }
+                c => {
+                    return Err(self.error_at(
+                        line,
+                        column,
+                        PgnErrorKind::UnexpectedToken(c.to_string()),
+                    ))
+                }
+            };
+
+            return Ok((token, line, column));
+        }
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> String {
+        let mut taken = String::new();
+        while self.column < self.line.len() && predicate(self.line[self.column]) {
+            taken.push(self.line[self.column]);
+            self.column += 1;
+        }
+        taken
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chessmove, color, pieces::piece};
+
+    fn read_all(pgn: &str) -> Vec<Result<PgnGame, PgnError>> {
+        PgnReader::new(pgn.as_bytes()).collect()
+    }
+
+    const TWO_GAMES: &str = r#"[Event "Casual"]
+[Site "?"]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6?? 4. Qxf7# 1-0
+
+[Event "Second"]
+[Result "*"]
+
+1.d4 d5 *
+"#;
+
+    #[test]
+    fn reads_multiple_games() {
+        let games = read_all(TWO_GAMES);
+        assert_eq!(2, games.len());
+
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(Some("Casual"), first.tag("Event"));
+        assert_eq!(Some(GameResult::WhiteWins), first.result);
+        assert_eq!(7, first.moves.len());
+        assert_eq!("Qxf7#", first.moves[6].san);
+        assert_eq!(vec![4], first.moves[5].nags);
+
+        let mut game = first.starting_position().unwrap();
+        for mv in first.mainline() {
+            game.make_move(mv);
+        }
+        assert!(game.legal_moves().is_empty());
+
+        let second = games[1].as_ref().unwrap();
+        assert_eq!(Some(GameResult::Undecided), second.result);
+        assert_eq!(
+            vec![
+                chessmove::ChessMove {
+                    from: (4, 2),
+                    to: (4, 4),
+                    promotion: None
+                },
+                chessmove::ChessMove {
+                    from: (4, 7),
+                    to: (4, 5),
+                    promotion: None
+                }
+            ],
+            second.mainline()
+        );
+    }
+
+    #[test]
+    fn comments_nags_and_variations() {
+        let pgn = r#"{Game comment} 1. e4 {best by test} $1 e5 (1... c5 {Sicilian} 2. Nf3 (2. c3) d6) (; rest of line
+ {French} 1... e6) 2. Nf3 1/2-1/2"#;
+        let games = read_all(pgn);
+        let game = games[0].as_ref().unwrap();
+
+        assert_eq!(vec!["Game comment".to_string()], game.comments);
+        assert_eq!(vec!["best by test".to_string()], game.moves[0].comments);
+        assert_eq!(vec![1], game.moves[0].nags);
+        assert_eq!(3, game.moves.len());
+        assert_eq!(Some(GameResult::Draw), game.result);
+
+        let variations = &game.moves[1].variations;
+        assert_eq!(2, variations.len());
+        assert_eq!(
+            vec!["c5", "Nf3", "d6"],
+            variations[0]
+                .iter()
+                .map(|mv| mv.san.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["Sicilian".to_string()], variations[0][0].comments);
+        assert_eq!("c3", variations[0][1].variations[0][0].san);
+        assert_eq!(
+            vec!["rest of line".to_string(), "French".to_string()],
+            variations[1][0].pre_comments
+        );
+        assert_eq!("e6", variations[1][0].san);
+    }
+
+    #[test]
+    fn starts_from_fen_tag() {
+        let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40"]
+
+40... Kd7 41. e4 *"#;
+        let games = read_all(pgn);
+        let pgn_game = games[0].as_ref().unwrap();
+        assert_eq!(2, pgn_game.moves.len());
+
+        let mut game = pgn_game.starting_position().unwrap();
+        assert_eq!(color::Color::BLACK, *game.side_to_move());
+        for mv in pgn_game.mainline() {
+            game.make_move(mv);
+        }
+        assert_eq!(41, game.full_moves());
+    }
+
+    #[test]
+    fn promotion_and_game_without_result() {
+        let pgn = r#"[FEN "8/4P3/8/8/8/8/k7/4K3 w - - 0 1"]
+
+1. e8=Q Kb2
+
+[Event "next"]
+1. e4 *"#;
+        let games = read_all(pgn);
+        assert_eq!(2, games.len());
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(None, first.result);
+        assert_eq!(
+            Some(piece::PromotionPiece::Queen),
+            first.moves[0].chessmove.promotion
+        );
+        assert_eq!(Some("next"), games[1].as_ref().unwrap().tag("Event"));
+    }
+
+    #[test]
+    fn illegal_move_reports_location_and_recovers() {
+        let pgn = r#"[Event "broken"]
+
+1. e4 e5
+2. Ke3 Nc6 *
+
+[Event "fine"]
+
+1. e4 *
+"#;
+        let games = read_all(pgn);
+        assert_eq!(2, games.len());
+        match &games[0] {
+            Err(PgnError {
+                line: 4,
+                column: 4,
+                kind: PgnErrorKind::InvalidMove(_),
+            }) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(Some("fine"), games[1].as_ref().unwrap().tag("Event"));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let cases: [(&str, usize, usize, PgnErrorKind); 5] = [
+            (
+                "[Event \"x]\n1. e4 *",
+                1,
+                8,
+                PgnErrorKind::UnterminatedString,
+            ),
+            (
+                "1. e4 {never closed",
+                1,
+                7,
+                PgnErrorKind::UnterminatedComment,
+            ),
+            ("[Event x]\n*", 1, 8, PgnErrorKind::InvalidTag),
+            ("$3 1. e4 *", 1, 1, PgnErrorKind::AnnotationWithoutMove),
+            (
+                "1. e4 (1. d4 *",
+                1,
+                14,
+                PgnErrorKind::UnexpectedToken("*".to_string()),
+            ),
+        ];
+        for (pgn, line, column, kind) in cases.iter() {
+            let games = read_all(pgn);
+            match &games[0] {
+                Err(e) => {
+                    assert_eq!(*line, e.line, "{}", pgn);
+                    assert_eq!(*column, e.column, "{}", pgn);
+                    assert_eq!(*kind, e.kind, "{}", pgn);
+                }
+                Ok(_) => panic!("{} should not parse", pgn),
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_fen_tag() {
+        let games = read_all("[Event \"x\"]\n[FEN \"8/8 w - -\"]\n\n*");
+        match &games[0] {
+            Err(PgnError {
+                line: 2,
+                column: 2,
+                kind: PgnErrorKind::InvalidFen(_),
+            }) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(read_all("").is_empty());
+        assert!(read_all("\n\n% escaped line\n").is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+use super::GameResult;
+use crate::{color, fen, game};
+use std::fmt;
+
+const MAX_LINE_LENGTH: usize = 80;
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+// Exports the moves played in a game as PGN. Use `to_string()` or `write!` to get the text.
+pub struct PgnWriter<'a> {
+    game: &'a game::Game,
+    tags: Vec<(String, String)>,
+    result: Option<GameResult>,
+}
+
+impl<'a> PgnWriter<'a> {
+    pub fn new(game: &'a game::Game) -> Self {
+        Self {
+            game,
+            tags: vec![],
+            result: None,
+        }
+    }
+
+    // overrides a tag of the Seven Tag Roster or adds an extra one
+    pub fn tag(mut self, name: &str, value: &str) -> Self {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    // without an explicit result, it is derived from `Game::outcome` and "*" if there is none
+    pub fn result(mut self, result: GameResult) -> Self {
+        self.result = Some(result);
+        self
+    }
+
+    fn game_result(&self) -> GameResult {
+        if let Some(result) = self.result {
+            return result;
+        }
+        match self.game.outcome() {
+            None => GameResult::Undecided,
+            Some(outcome) => match outcome.winner() {
+                None => GameResult::Draw,
+                Some(color::Color::WHITE) => GameResult::WhiteWins,
+                Some(color::Color::BLACK) => GameResult::BlackWins,
+            },
+        }
+    }
+
+    fn all_tags(&self, initial: &game::Game, result: GameResult) -> Vec<(String, String)> {
+        let mut tags = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|(name, default)| {
+                let value = if *name == "Result" {
+                    result.to_string()
+                } else {
+                    self.tag_value(name).unwrap_or(default).to_string()
+                };
+                (name.to_string(), value)
+            })
+            .collect::<Vec<_>>();
+
+        let initial_fen = initial.to_fen();
+        if initial_fen != fen::INITIAL_FEN {
+            for (name, value) in &[("SetUp", "1"), ("FEN", initial_fen.as_str())] {
+                if self.tag_value(name).is_none() {
+                    tags.push((name.to_string(), value.to_string()));
+                }
+            }
+        }
+
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.iter().any(|(roster, _)| roster == name) {
+                tags.push((name.clone(), value.clone()));
+            }
+        }
+
+        tags
+    }
+
+    fn tag_value(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl<'a> fmt::Display for PgnWriter<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut position = self.game.initial_position();
+        let result = self.game_result();
+
+        for (name, value) in self.all_tags(&position, result) {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = vec![];
+        for (i, mv) in self.game.move_history().iter().enumerate() {
+            match position.side_to_move() {
+                color::Color::WHITE => tokens.push(format!("{}.", position.full_moves())),
+                color::Color::BLACK if i == 0 => {
+                    tokens.push(format!("{}...", position.full_moves()))
+                }
+                color::Color::BLACK => (),
+            }
+            tokens.push(position.move_to_san(mv));
+            position.make_move(*mv);
+        }
+        tokens.push(result.to_string());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", token)?;
+            line_length += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::reader::PgnReader;
+    use super::*;
+
+    #[test]
+    fn seven_tag_roster_and_result() {
+        let mut game = game::Game::new();
+        game::play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+
+        let pgn = PgnWriter::new(&game)
+            .tag("White", "Fool")
+            .tag("Annotator", "bot")
+            .to_string();
+
+        assert_eq!(
+            "[Event \"?\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Fool\"]\n\
+             [Black \"?\"]\n\
+             [Result \"0-1\"]\n\
+             [Annotator \"bot\"]\n\
+             \n\
+             1. f3 e5 2. g4 Qh4# 0-1\n",
+            pgn
+        );
+    }
+
+    #[test]
+    fn custom_position_with_black_to_move() {
+        let mut game = game::Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 3 40").unwrap();
+        game::play(&mut game, &["Kd7", "e4", "Ke6"]);
+
+        let pgn = PgnWriter::new(&game)
+            .tag("Event", "Say \"hi\"")
+            .result(GameResult::Draw)
+            .to_string();
+
+        assert!(pgn.contains("[Event \"Say \\\"hi\\\"\"]\n"));
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 3 40\"]\n"));
+        assert!(pgn.ends_with("\n40... Kd7 41. e4 Ke6 1/2-1/2\n"));
+    }
+
+    #[test]
+    fn wraps_lines_and_reads_back() {
+        let mut game = game::Game::new();
+        let moves = [
+            "Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1", "Ng8", "Nc3", "Nc6", "Nb1", "Nb8",
+            "Nc3", "Nc6", "Nb1", "Nb8", "e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O",
+            "Be7", "Re1", "b5", "Bb3", "d6", "c3", "O-O",
+        ];
+        game::play(&mut game, &moves);
+
+        let pgn = PgnWriter::new(&game).to_string();
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert!(pgn.ends_with(" *\n"));
+
+        let read = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(game.move_history(), read.mainline());
+        assert_eq!(
+            moves.to_vec(),
+            read.moves
+                .iter()
+                .map(|mv| mv.san.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn stalemate_is_a_draw() {
+        let mut game = game::Game::from_fen("7k/8/8/5Q2/8/8/8/K7 w - - 0 1").unwrap();
+        game::play(&mut game, &["Qf7"]);
+        let pgn = PgnWriter::new(&game).to_string();
+        assert!(pgn.contains("[Result \"1/2-1/2\"]"));
+        assert!(pgn.ends_with("1. Qf7 1/2-1/2\n"));
+    }
+}
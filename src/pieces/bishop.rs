@@ -32,19 +32,20 @@ impl piece::Piece for Bishop {
         piece::PieceEnum::BISHOP
     }
 
-    fn moves_ignoring_pins(
+    fn generate_moves_ignoring_pins(
         &self,
         board: &board::Board,
         _en_passant: &Option<position::Position>,
         _king_pos: position::Position,
-    ) -> Vec<chessmove::ChessMove> {
-        sliding_moves::diagonal_sliding(self, board)
+        moves: &mut chessmove::MoveList,
+    ) {
+        sliding_moves::diagonal_sliding(self, board, moves)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::{knight, pawn};
+    use super::super::{knight, pawn, piece::Piece};
     use super::*;
     use std::iter;
 
@@ -124,6 +125,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::vec_init_then_push)]
     fn bishop_top_middle_with_obstacles_attacks() {
         let mut empty_board = board::Board::empty();
 
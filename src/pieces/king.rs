@@ -61,31 +61,31 @@ impl piece::Piece for King {
         attacked_positions
     }
 
-    fn moves_ignoring_pins(
+    fn generate_moves_ignoring_pins(
         &self,
         board: &board::Board,
         _en_passant: &Option<position::Position>,
         _king_pos: position::Position,
-    ) -> Vec<chessmove::ChessMove> {
+        moves: &mut chessmove::MoveList,
+    ) {
         let position = self.position;
-        let mut chessmoves = vec![];
 
         if position.0 != 1 {
             self.move_if_empty_or_enemy(
-                &mut chessmoves,
+                moves,
                 board,
                 position::Position(position.0 - 1, position.1),
             );
             if position.1 != 8 {
                 self.move_if_empty_or_enemy(
-                    &mut chessmoves,
+                    moves,
                     board,
                     position::Position(position.0 - 1, position.1 + 1),
                 );
             }
             if position.1 != 1 {
                 self.move_if_empty_or_enemy(
-                    &mut chessmoves,
+                    moves,
                     board,
                     position::Position(position.0 - 1, position.1 - 1),
                 );
@@ -94,20 +94,20 @@ impl piece::Piece for King {
 
         if position.0 != 8 {
             self.move_if_empty_or_enemy(
-                &mut chessmoves,
+                moves,
                 board,
                 position::Position(position.0 + 1, position.1),
             );
             if position.1 != 8 {
                 self.move_if_empty_or_enemy(
-                    &mut chessmoves,
+                    moves,
                     board,
                     position::Position(position.0 + 1, position.1 + 1),
                 );
             }
             if position.1 != 1 {
                 self.move_if_empty_or_enemy(
-                    &mut chessmoves,
+                    moves,
                     board,
                     position::Position(position.0 + 1, position.1 - 1),
                 );
@@ -116,7 +116,7 @@ impl piece::Piece for King {
 
         if position.1 != 1 {
             self.move_if_empty_or_enemy(
-                &mut chessmoves,
+                moves,
                 board,
                 position::Position(position.0, position.1 - 1),
             );
@@ -124,20 +124,18 @@ impl piece::Piece for King {
 
         if position.1 != 8 {
             self.move_if_empty_or_enemy(
-                &mut chessmoves,
+                moves,
                 board,
                 position::Position(position.0, position.1 + 1),
             );
         }
-
-        chessmoves
     }
 }
 
 impl King {
     fn move_if_empty_or_enemy(
         &self,
-        positions: &mut Vec<chessmove::ChessMove>,
+        positions: &mut chessmove::MoveList,
         board: &board::Board,
         position: position::Position,
     ) {
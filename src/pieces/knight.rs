@@ -8,12 +8,14 @@ pub struct Knight {
 }
 
 impl piece::Piece for Knight {
-    fn moves_ignoring_pins(
+    #[allow(clippy::manual_range_contains)]
+    fn generate_moves_ignoring_pins(
         &self,
         board: &board::Board,
         _en_passant: &Option<position::Position>,
         _king_pos: position::Position,
-    ) -> Vec<chessmove::ChessMove> {
+        moves: &mut chessmove::MoveList,
+    ) {
         let positions: [[i8; 2]; 8] = [
             [1, 2],
             [1, -2],
@@ -25,8 +27,6 @@ impl piece::Piece for Knight {
             [-2, -1],
         ];
 
-        let mut attacks = Vec::new();
-
         for position in &positions {
             let file = position[0] + self.position.0 as i8;
             let rank = position[1] + self.position.1 as i8;
@@ -40,21 +40,20 @@ impl piece::Piece for Knight {
                 match board.get_square(position::Position(file as u8, rank as u8)) {
                     Some(piece) => {
                         if *piece.color() != self.color {
-                            attacks.push(mv)
+                            moves.push(mv)
                         }
                     }
-                    None => attacks.push(mv),
+                    None => moves.push(mv),
                 }
             }
         }
-
-        attacks
     }
 
     fn piece(&self) -> piece::PieceEnum {
         piece::PieceEnum::KNIGHT
     }
 
+    #[allow(clippy::manual_range_contains)]
     fn attacks(
         &self,
         _board: &board::Board,
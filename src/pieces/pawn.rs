@@ -17,6 +17,7 @@ impl piece::Piece for Pawn {
         &mut self.position
     }
 
+    #[allow(clippy::needless_return)]
     fn attacks(
         &self,
         _board: &board::Board,
@@ -46,17 +47,16 @@ impl piece::Piece for Pawn {
         }
     }
 
-    fn moves_ignoring_pins(
+    fn generate_moves_ignoring_pins(
         &self,
         board: &board::Board,
         en_passant: &Option<position::Position>,
         king_pos: position::Position,
-    ) -> Vec<chessmove::ChessMove> {
-        let mut moves = vec![];
-        self.add_attack_moves(board, &mut moves);
-        self.add_forward_moves(board, &mut moves);
-        self.add_en_passant_moves(&mut moves, en_passant, king_pos, board);
-        self.transform_mvs_to_chessmoves(&moves)
+        moves: &mut chessmove::MoveList,
+    ) {
+        self.add_attack_moves(board, moves);
+        self.add_forward_moves(board, moves);
+        self.add_en_passant_moves(moves, en_passant, king_pos, board);
     }
 
     fn position(&self) -> &position::Position {
@@ -69,36 +69,30 @@ impl piece::Piece for Pawn {
 }
 
 impl Pawn {
-    fn transform_mvs_to_chessmoves(
-        &self,
-        moves: &Vec<position::Position>,
-    ) -> Vec<chessmove::ChessMove> {
-        let mut chessmoves = vec![];
-
-        for mv in moves {
-            if mv.1 == 1 || mv.1 == 8 {
-                for pc in piece::PROMOTION_PIECES.iter().copied() {
-                    chessmoves.push(chessmove::ChessMove {
-                        from: (self.position().0, self.position().1),
-                        to: (mv.0, mv.1),
-                        promotion: Some(pc),
-                    });
-                }
-            } else {
-                chessmoves.push(chessmove::ChessMove {
-                    from: (self.position().0, self.position().1),
-                    to: (mv.0, mv.1),
-                    promotion: None,
-                })
+    // a move to the last rank is pushed once for every promotion piece
+    fn push_move(&self, to: position::Position, moves: &mut chessmove::MoveList) {
+        let from = (self.position().0, self.position().1);
+        if to.1 == 1 || to.1 == 8 {
+            for pc in piece::PROMOTION_PIECES.iter().copied() {
+                moves.push(chessmove::ChessMove {
+                    from,
+                    to: (to.0, to.1),
+                    promotion: Some(pc),
+                });
             }
+        } else {
+            moves.push(chessmove::ChessMove {
+                from,
+                to: (to.0, to.1),
+                promotion: None,
+            })
         }
-
-        chessmoves
     }
 
+    #[allow(clippy::needless_borrow)]
     fn add_en_passant_moves(
         &self,
-        moves: &mut Vec<position::Position>,
+        moves: &mut chessmove::MoveList,
         en_passant: &Option<position::Position>,
         king_pos: position::Position,
         board: &board::Board,
@@ -113,25 +107,29 @@ impl Pawn {
                 && ((en_passant.0 == self.position.0 - 1) || (en_passant.0 == self.position.0 + 1))
                 && !self.cannot_en_passant_due_to_discovered_check(king_pos, *en_passant, &board)
             {
-                moves.push(*en_passant);
+                self.push_move(*en_passant, moves);
             }
         }
     }
 
-    fn add_attack_moves(&self, board: &board::Board, moves: &mut Vec<position::Position>) {
-        moves.append(
-            &mut self
-                .attacks(board, position::Position(0, 0))
-                .into_iter()
-                .filter(|pos| match board.get_square(*pos) {
-                    None => false,
-                    Some(piece) => *piece.color() != *self.color(),
-                })
-                .collect::<Vec<_>>(),
-        );
+    fn add_attack_moves(&self, board: &board::Board, moves: &mut chessmove::MoveList) {
+        let rank = match self.color() {
+            color::Color::WHITE => self.position().1 + 1,
+            color::Color::BLACK => self.position().1 - 1,
+        };
+        for file in [self.position().0 - 1, self.position().0 + 1].iter() {
+            if !(1..=8).contains(file) {
+                continue;
+            }
+            let target = position::Position(*file, rank);
+            match board.get_square(target) {
+                Some(piece) if *piece.color() != *self.color() => self.push_move(target, moves),
+                _ => (),
+            }
+        }
     }
 
-    fn add_forward_moves(&self, board: &board::Board, moves: &mut Vec<position::Position>) {
+    fn add_forward_moves(&self, board: &board::Board, moves: &mut chessmove::MoveList) {
         let (initial, initial_one, initial_two, last, moving) = match self.color() {
             color::Color::WHITE => (2, 3, 4, 8, 1),
             color::Color::BLACK => (7, 6, 5, 1, -1),
@@ -141,7 +139,7 @@ impl Pawn {
             && board.is_empty(position::Position(self.position().0, initial_one))
             && board.is_empty(position::Position(self.position().0, initial_two))
         {
-            moves.push(position::Position(self.position().0, initial_two));
+            self.push_move(position::Position(self.position().0, initial_two), moves);
         }
 
         if self.position().1 != last
@@ -150,10 +148,10 @@ impl Pawn {
                 (self.position().1 as i8 + moving) as u8,
             ))
         {
-            moves.push(position::Position(
-                self.position().0,
-                (self.position().1 as i8 + moving) as u8,
-            ));
+            self.push_move(
+                position::Position(self.position().0, (self.position().1 as i8 + moving) as u8),
+                moves,
+            );
         }
     }
 
@@ -1,5 +1,5 @@
-use super::{bishop, king, knight, pawn, position, queen, relative_position, rook};
-use crate::{board, chessmove, color};
+use super::{bishop, king, knight, pawn, position, queen, rook};
+use crate::{attack, board, chessmove, color};
 use std::fmt;
 
 pub trait Piece: fmt::Debug {
@@ -22,110 +22,59 @@ pub trait Piece: fmt::Debug {
 
     fn piece(&self) -> PieceEnum;
 
-    fn moves_ignoring_pins(
+    // pushes onto `moves` instead of returning a new vector, so it does not allocate
+    fn generate_moves_ignoring_pins(
         &self,
         board: &board::Board,
         en_passant: &Option<position::Position>,
         king_pos: position::Position,
-    ) -> Vec<chessmove::ChessMove>;
+        moves: &mut chessmove::MoveList,
+    );
 
-    fn moves(
+    fn moves_ignoring_pins(
         &self,
         board: &board::Board,
-        king: position::Position,
         en_passant: &Option<position::Position>,
+        king_pos: position::Position,
     ) -> Vec<chessmove::ChessMove> {
-        let moves_ignoring_pins = self.moves_ignoring_pins(board, en_passant, king);
-
-        match self.valid_moves_during_pin(board, king) {
-            None => moves_ignoring_pins,
-            Some(valid_moves_during_pin) => moves_ignoring_pins
-                .into_iter()
-                .filter(|mv| {
-                    let pos = position::Position((mv.to).0, (mv.to).1);
-                    valid_moves_during_pin.contains(&pos)
-                })
-                .collect::<Vec<_>>(),
-        }
+        let mut moves = chessmove::MoveList::new();
+        self.generate_moves_ignoring_pins(board, en_passant, king_pos, &mut moves);
+        moves.to_vec()
     }
 
-    fn valid_moves_during_pin(
+    fn generate_moves(
         &self,
         board: &board::Board,
         king: position::Position,
-    ) -> Option<Vec<position::Position>> {
-        if *self.position() == king {
-            return None;
-        }
-
-        let (mover, direction) =
-            match relative_position::get_line_to_other_piece(self.position(), &king) {
-                None => return None,
-                Some(v) => v,
-            };
-
-        let mut moves = vec![];
-
-        let mut new_file = self.position().0 as i8;
-        let mut new_rank = self.position().1 as i8;
-
-        loop {
-            new_file += mover.0;
-            new_rank += mover.1;
-
-            let new_position = position::Position(new_file as u8, new_rank as u8);
-
-            match board.get_square(new_position) {
-                None => moves.push(new_position),
-                Some(piece) => {
-                    if piece.piece() != PieceEnum::KING {
-                        return None;
-                    } else if *piece.color() == *self.color() {
-                        break;
-                    } else {
-                        return None;
-                    }
-                }
-            }
-        }
-
-        let mut new_file = self.position().0 as i8;
-        let mut new_rank = self.position().1 as i8;
-
-        loop {
-            new_file += -mover.0;
-            new_rank += -mover.1;
-
-            if new_file < 1 || new_file > 8 || new_rank < 1 || new_rank > 8 {
-                return None;
-            }
-
-            let new_position = position::Position(new_file as u8, new_rank as u8);
-
-            match board.get_square(new_position) {
-                None => moves.push(new_position),
-                Some(piece) => {
-                    if *piece.color() == *self.color() {
-                        return None;
-                    }
-                    match (piece.piece(), &direction) {
-                        (PieceEnum::QUEEN, _)
-                        | (PieceEnum::ROOK, relative_position::Direction::STRAIGHT(..))
-                        | (PieceEnum::BISHOP, relative_position::Direction::DIAGONAL(..)) => {
-                            moves.push(new_position);
-                            break;
-                        }
-                        (_, _) => return None,
-                    }
-                }
-            }
+        en_passant: &Option<position::Position>,
+        moves: &mut chessmove::MoveList,
+    ) {
+        let start = moves.len();
+        self.generate_moves_ignoring_pins(board, en_passant, king, moves);
+
+        let pin = attack::pin_rays(board, king, *self.color())
+            [self.position().bit().trailing_zeros() as usize];
+        if pin != 0 {
+            moves.retain_from(start, |mv| {
+                pin & position::Position((mv.to).0, (mv.to).1).bit() != 0
+            });
         }
+    }
 
-        Some(moves)
+    fn moves(
+        &self,
+        board: &board::Board,
+        king: position::Position,
+        en_passant: &Option<position::Position>,
+    ) -> Vec<chessmove::ChessMove> {
+        let mut moves = chessmove::MoveList::new();
+        self.generate_moves(board, king, en_passant, &mut moves);
+        moves.to_vec()
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceEnum {
     PAWN,
     ROOK,
@@ -136,6 +85,7 @@ pub enum PieceEnum {
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PromotionPiece {
     Rook,
     Knight,
@@ -150,6 +100,94 @@ pub static PROMOTION_PIECES: [PromotionPiece; 4] = [
     PromotionPiece::Queen,
 ];
 
+// what the board stores on an occupied square
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColoredPiece {
+    pub piece: PieceEnum,
+    pub color: color::Color,
+}
+
+impl ColoredPiece {
+    pub fn placed_at(self, position: position::Position) -> PlacedPiece {
+        PlacedPiece {
+            piece: self.piece,
+            color: self.color,
+            position,
+        }
+    }
+}
+
+// a board piece together with its square. Its behaviour is picked by matching on `piece`,
+// so no allocation is needed to ask it for its moves or attacks.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PlacedPiece {
+    pub piece: PieceEnum,
+    pub color: color::Color,
+    pub position: position::Position,
+}
+
+impl PlacedPiece {
+    fn with_piece<T>(&self, f: impl FnOnce(&dyn Piece) -> T) -> T {
+        let (color, position) = (self.color, self.position);
+        match self.piece {
+            PieceEnum::PAWN => f(&pawn::Pawn { color, position }),
+            PieceEnum::ROOK => f(&rook::Rook { color, position }),
+            PieceEnum::KNIGHT => f(&knight::Knight { color, position }),
+            PieceEnum::BISHOP => f(&bishop::Bishop { color, position }),
+            PieceEnum::QUEEN => f(&queen::Queen { color, position }),
+            PieceEnum::KING => f(&king::King { color, position }),
+        }
+    }
+}
+
+impl Piece for PlacedPiece {
+    fn position(&self) -> &position::Position {
+        &self.position
+    }
+
+    fn mut_position(&mut self) -> &mut position::Position {
+        &mut self.position
+    }
+
+    fn attacks(
+        &self,
+        board: &board::Board,
+        enemy_king_pos: position::Position,
+    ) -> Vec<position::Position> {
+        self.with_piece(|piece| piece.attacks(board, enemy_king_pos))
+    }
+
+    fn color(&self) -> &color::Color {
+        &self.color
+    }
+
+    fn piece(&self) -> PieceEnum {
+        self.piece
+    }
+
+    fn generate_moves_ignoring_pins(
+        &self,
+        board: &board::Board,
+        en_passant: &Option<position::Position>,
+        king_pos: position::Position,
+        moves: &mut chessmove::MoveList,
+    ) {
+        self.with_piece(|piece| {
+            piece.generate_moves_ignoring_pins(board, en_passant, king_pos, moves)
+        })
+    }
+}
+
+pub fn promotion_piece_to_piece_enum(promotion_piece: PromotionPiece) -> PieceEnum {
+    match promotion_piece {
+        PromotionPiece::Rook => PieceEnum::ROOK,
+        PromotionPiece::Knight => PieceEnum::KNIGHT,
+        PromotionPiece::Bishop => PieceEnum::BISHOP,
+        PromotionPiece::Queen => PieceEnum::QUEEN,
+    }
+}
+
 pub fn promotion_piece_to_piece(
     promotion_piece: PromotionPiece,
     color: color::Color,
@@ -183,6 +221,20 @@ mod tests {
     use super::super::{bishop, king, knight, pawn, queen};
     use super::*;
 
+    // the squares of the pin ray of the white piece on `position`, which include its own
+    fn pin_ray(
+        board: &board::Board,
+        king: position::Position,
+        position: position::Position,
+    ) -> Vec<position::Position> {
+        let ray = attack::pin_rays(board, king, color::Color::WHITE)
+            [position.bit().trailing_zeros() as usize];
+        (0..64)
+            .filter(|index| ray & 1 << index != 0)
+            .map(position::Position::from_bit_index)
+            .collect()
+    }
+
     #[test]
     fn pinned_knight() {
         let knight = knight::Knight {
@@ -206,27 +258,21 @@ mod tests {
         board.set_square(Some(Box::new(knight)), position::Position(4, 4));
         board.set_square(Some(Box::new(black_queen)), position::Position(4, 8));
 
-        if let Some(knight) = board.get_square(position::Position(4, 4)) {
-            if let Some(actual) = knight.valid_moves_during_pin(&board, position::Position(4, 1)) {
-                let expected = vec![
-                    position::Position(4, 2),
-                    position::Position(4, 3),
-                    position::Position(4, 5),
-                    position::Position(4, 6),
-                    position::Position(4, 7),
-                    position::Position(4, 8),
-                ];
-
-                assert_eq!(expected.len(), actual.len());
-
-                for square in expected {
-                    assert!(actual.contains(&square));
-                }
-            } else {
-                panic!();
-            }
-        } else {
-            panic!();
+        let actual = pin_ray(&board, position::Position(4, 1), position::Position(4, 4));
+        let expected = vec![
+            position::Position(4, 2),
+            position::Position(4, 3),
+            position::Position(4, 4),
+            position::Position(4, 5),
+            position::Position(4, 6),
+            position::Position(4, 7),
+            position::Position(4, 8),
+        ];
+
+        assert_eq!(expected.len(), actual.len());
+
+        for square in expected {
+            assert!(actual.contains(&square));
         }
     }
 
@@ -253,13 +299,7 @@ mod tests {
         board.set_square(Some(Box::new(knight)), position::Position(4, 4));
         board.set_square(Some(Box::new(black_knight)), position::Position(4, 8));
 
-        if let Some(knight) = board.get_square(position::Position(4, 4)) {
-            assert!(knight
-                .valid_moves_during_pin(&board, position::Position(4, 1))
-                .is_none());
-        } else {
-            panic!();
-        }
+        assert!(pin_ray(&board, position::Position(4, 1), position::Position(4, 4)).is_empty());
     }
 
     #[test]
@@ -291,13 +331,7 @@ mod tests {
         board.set_square(Some(Box::new(black_queen)), position::Position(4, 8));
         board.set_square(Some(Box::new(black_pawn)), position::Position(4, 7));
 
-        if let Some(knight) = board.get_square(position::Position(4, 4)) {
-            assert!(knight
-                .valid_moves_during_pin(&board, position::Position(4, 1))
-                .is_none());
-        } else {
-            panic!();
-        }
+        assert!(pin_ray(&board, position::Position(4, 1), position::Position(4, 4)).is_empty());
     }
 
     #[test]
@@ -324,11 +358,7 @@ mod tests {
         board.set_square(Some(Box::new(knight)), position::Position(4, 4));
         board.set_square(Some(Box::new(black_queen)), position::Position(4, 8));
 
-        if let Some(knight) = board.get_square(position::Position(4, 4)) {
-            assert!(knight.valid_moves_during_pin(&board, king_pos).is_none());
-        } else {
-            panic!();
-        }
+        assert!(pin_ray(&board, king_pos, position::Position(4, 4)).is_empty());
     }
 
     #[test]
@@ -356,24 +386,18 @@ mod tests {
         board.set_square(Some(Box::new(knight)), knight_pos);
         board.set_square(Some(Box::new(black_bishop)), position::Position(6, 6));
 
-        if let Some(knight) = board.get_square(knight_pos) {
-            if let Some(actual) = knight.valid_moves_during_pin(&board, king_pos) {
-                let expected = vec![
-                    position::Position(2, 2),
-                    position::Position(3, 3),
-                    position::Position(5, 5),
-                    position::Position(6, 6),
-                ];
-                assert_eq!(expected.len(), actual.len());
-
-                for square in expected {
-                    assert!(actual.contains(&square));
-                }
-            } else {
-                panic!();
-            }
-        } else {
-            panic!();
+        let actual = pin_ray(&board, king_pos, knight_pos);
+        let expected = vec![
+            position::Position(2, 2),
+            position::Position(3, 3),
+            position::Position(4, 4),
+            position::Position(5, 5),
+            position::Position(6, 6),
+        ];
+        assert_eq!(expected.len(), actual.len());
+
+        for square in expected {
+            assert!(actual.contains(&square));
         }
     }
 
@@ -409,11 +433,7 @@ mod tests {
         board.set_square(Some(Box::new(black_bishop)), position::Position(6, 6));
         board.set_square(Some(Box::new(black_knight)), black_knight_pos);
 
-        if let Some(knight) = board.get_square(knight_pos) {
-            assert!(knight.valid_moves_during_pin(&board, king_pos).is_none());
-        } else {
-            panic!();
-        }
+        assert!(pin_ray(&board, king_pos, knight_pos).is_empty());
     }
 
     #[test]
@@ -433,13 +453,7 @@ mod tests {
         board.set_square(Some(Box::new(king)), position::Position(4, 1));
         board.set_square(Some(Box::new(knight)), position::Position(4, 4));
 
-        if let Some(knight) = board.get_square(position::Position(4, 4)) {
-            assert!(knight
-                .valid_moves_during_pin(&board, position::Position(4, 1))
-                .is_none());
-        } else {
-            panic!();
-        }
+        assert!(pin_ray(&board, position::Position(4, 1), position::Position(4, 4)).is_empty());
     }
 
     // TODO: incorporate new moves function into these tests / write new ones
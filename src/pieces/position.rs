@@ -1,3 +1,15 @@
 // 1: file, 2: rank
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position(pub u8, pub u8);
+
+impl Position {
+    // the square as a bit of a u64 set of squares, a1 is the lowest bit and h8 the highest
+    pub(crate) fn bit(self) -> u64 {
+        1 << ((self.1 - 1) * 8 + self.0 - 1)
+    }
+
+    pub(crate) fn from_bit_index(index: u32) -> Self {
+        Position((index % 8) as u8 + 1, (index / 8) as u8 + 1)
+    }
+}
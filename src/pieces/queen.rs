@@ -24,16 +24,15 @@ impl piece::Piece for Queen {
         piece::PieceEnum::QUEEN
     }
 
-    fn moves_ignoring_pins(
+    fn generate_moves_ignoring_pins(
         &self,
         board: &board::Board,
         _en_passant: &Option<position::Position>,
         _king_pos: position::Position,
-    ) -> Vec<chessmove::ChessMove> {
-        let mut straight_moves = sliding_moves::straight_sliding(self, board);
-        let mut diagonal_moves = sliding_moves::diagonal_sliding(self, board);
-        straight_moves.append(&mut diagonal_moves);
-        straight_moves
+        moves: &mut chessmove::MoveList,
+    ) {
+        sliding_moves::straight_sliding(self, board, moves);
+        sliding_moves::diagonal_sliding(self, board, moves);
     }
 
     fn attacks(
@@ -52,6 +51,7 @@ impl piece::Piece for Queen {
 
 #[cfg(test)]
 mod tests {
+    use super::super::piece::Piece;
     use super::*;
     use std::iter;
 
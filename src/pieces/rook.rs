@@ -32,19 +32,20 @@ impl piece::Piece for Rook {
         sliding_attacks::straight_attacks(self.position, board, enemy_king_pos)
     }
 
-    fn moves_ignoring_pins(
+    fn generate_moves_ignoring_pins(
         &self,
         board: &board::Board,
         _en_passant: &Option<position::Position>,
         _king_pos: position::Position,
-    ) -> Vec<chessmove::ChessMove> {
-        sliding_moves::straight_sliding(self, board)
+        moves: &mut chessmove::MoveList,
+    ) {
+        sliding_moves::straight_sliding(self, board, moves)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::{bishop, knight, pawn};
+    use super::super::{bishop, knight, pawn, piece::Piece};
     use super::*;
     use std::iter;
 
@@ -94,6 +95,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::vec_init_then_push)]
     fn rook_middle_with_obstacles() {
         let mut empty_board = board::Board::empty();
 
@@ -1,5 +1,5 @@
 use super::position;
-use crate::pieces::piece;
+use crate::pieces::piece::{self, Piece};
 use crate::{board, chessmove};
 
 #[derive(PartialEq)]
@@ -11,7 +11,8 @@ enum Axis {
 pub fn straight_sliding(
     piece: &dyn piece::Piece,
     board: &board::Board,
-) -> Vec<chessmove::ChessMove> {
+    moves: &mut chessmove::MoveList,
+) {
     let moves_and_bounds = [
         (Axis::Horizontal, 1, 8),
         (Axis::Horizontal, -1, 1),
@@ -19,8 +20,6 @@ pub fn straight_sliding(
         (Axis::Vertical, -1, 1),
     ];
 
-    let mut moves = vec![];
-
     let mut move_piece = |file: u8, rank: u8| -> bool {
         let move_position = position::Position(file, rank);
         let mv = chessmove::ChessMove {
@@ -58,14 +57,13 @@ pub fn straight_sliding(
             }
         }
     }
-
-    moves
 }
 
 pub fn diagonal_sliding(
     piece: &dyn piece::Piece,
     board: &board::Board,
-) -> Vec<chessmove::ChessMove> {
+    moves: &mut chessmove::MoveList,
+) {
     let moves_and_bounds = [
         ((1, 1), (8, 8)),
         ((1, -1), (8, 1)),
@@ -73,8 +71,6 @@ pub fn diagonal_sliding(
         ((-1, -1), (1, 1)),
     ];
 
-    let mut moves = vec![];
-
     for entry in &moves_and_bounds {
         let mut current_file = piece.position().0 as i8;
         let mut current_rank = piece.position().1 as i8;
@@ -97,6 +93,4 @@ pub fn diagonal_sliding(
             }
         }
     }
-
-    moves
 }
@@ -1,63 +1,100 @@
 use super::{bishop, king, knight, pawn, piece, position, queen, rook};
-use crate::color;
+use crate::{color, error};
 
 pub trait ToPiece {
     fn to_piece(&self, position: position::Position) -> Option<Box<dyn piece::Piece>>;
+
+    // implementations that can fail should override this, the default assumes `to_piece` does not
+    fn try_to_piece(
+        &self,
+        position: position::Position,
+    ) -> Result<Option<Box<dyn piece::Piece>>, error::Error> {
+        Ok(self.to_piece(position))
+    }
 }
 
 impl ToPiece for u8 {
     fn to_piece(&self, position: position::Position) -> Option<Box<dyn piece::Piece>> {
-        match *self {
-            0 => None,
-            11 => Some(Box::new(pawn::Pawn {
+        match self.try_to_piece(position) {
+            Ok(piece) => piece,
+            Err(_) => panic!("Invalid piece number"),
+        }
+    }
+
+    fn try_to_piece(
+        &self,
+        position: position::Position,
+    ) -> Result<Option<Box<dyn piece::Piece>>, error::Error> {
+        let piece: Box<dyn piece::Piece> = match *self {
+            0 => return Ok(None),
+            11 => Box::new(pawn::Pawn {
                 color: color::Color::BLACK,
                 position,
-            })),
-            1 => Some(Box::new(pawn::Pawn {
+            }),
+            1 => Box::new(pawn::Pawn {
                 color: color::Color::WHITE,
                 position,
-            })),
-            12 => Some(Box::new(rook::Rook {
+            }),
+            12 => Box::new(rook::Rook {
                 color: color::Color::BLACK,
                 position,
-            })),
-            2 => Some(Box::new(rook::Rook {
+            }),
+            2 => Box::new(rook::Rook {
                 color: color::Color::WHITE,
                 position,
-            })),
-            13 => Some(Box::new(knight::Knight {
+            }),
+            13 => Box::new(knight::Knight {
                 color: color::Color::BLACK,
                 position,
-            })),
-            3 => Some(Box::new(knight::Knight {
+            }),
+            3 => Box::new(knight::Knight {
                 color: color::Color::WHITE,
                 position,
-            })),
-            14 => Some(Box::new(bishop::Bishop {
+            }),
+            14 => Box::new(bishop::Bishop {
                 color: color::Color::BLACK,
                 position,
-            })),
-            4 => Some(Box::new(bishop::Bishop {
+            }),
+            4 => Box::new(bishop::Bishop {
                 color: color::Color::WHITE,
                 position,
-            })),
-            15 => Some(Box::new(queen::Queen {
+            }),
+            15 => Box::new(queen::Queen {
                 color: color::Color::BLACK,
                 position,
-            })),
-            5 => Some(Box::new(queen::Queen {
+            }),
+            5 => Box::new(queen::Queen {
                 color: color::Color::WHITE,
                 position,
-            })),
-            16 => Some(Box::new(king::King {
+            }),
+            16 => Box::new(king::King {
                 color: color::Color::BLACK,
                 position,
-            })),
-            6 => Some(Box::new(king::King {
+            }),
+            6 => Box::new(king::King {
                 color: color::Color::WHITE,
                 position,
-            })),
-            _ => panic!("Invalid piece number"),
-        }
+            }),
+            code => return Err(error::Error::InvalidPieceCode(code)),
+        };
+        Ok(Some(piece))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_to_piece() {
+        let position = position::Position(5, 1);
+        let king = 6.try_to_piece(position).unwrap().unwrap();
+        assert_eq!(piece::PieceEnum::KING, king.piece());
+        assert_eq!(color::Color::WHITE, *king.color());
+        assert!(0.try_to_piece(position).unwrap().is_none());
+        assert_eq!(
+            error::Error::InvalidPieceCode(7),
+            7.try_to_piece(position).unwrap_err()
+        );
     }
 }
@@ -0,0 +1,362 @@
+use crate::pieces::{piece, piece::Piece, position};
+use crate::{chessmove, fen, game};
+use std::{error, fmt};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SanError {
+    InvalidSan(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::InvalidSan(san) => write!(f, "'{}' is not valid SAN", san),
+            SanError::IllegalMove(san) => write!(f, "'{}' is not a legal move", san),
+            SanError::AmbiguousMove(san) => write!(f, "'{}' matches more than one legal move", san),
+        }
+    }
+}
+
+impl error::Error for SanError {}
+
+pub fn piece_letter(piece: piece::PieceEnum) -> Option<char> {
+    match piece {
+        piece::PieceEnum::PAWN => None,
+        piece::PieceEnum::ROOK => Some('R'),
+        piece::PieceEnum::KNIGHT => Some('N'),
+        piece::PieceEnum::BISHOP => Some('B'),
+        piece::PieceEnum::QUEEN => Some('Q'),
+        piece::PieceEnum::KING => Some('K'),
+    }
+}
+
+pub fn promotion_letter(promotion: piece::PromotionPiece) -> char {
+    match promotion {
+        piece::PromotionPiece::Rook => 'R',
+        piece::PromotionPiece::Knight => 'N',
+        piece::PromotionPiece::Bishop => 'B',
+        piece::PromotionPiece::Queen => 'Q',
+    }
+}
+
+fn letter_to_piece(letter: char) -> Option<piece::PieceEnum> {
+    match letter {
+        'R' => Some(piece::PieceEnum::ROOK),
+        'N' => Some(piece::PieceEnum::KNIGHT),
+        'B' => Some(piece::PieceEnum::BISHOP),
+        'Q' => Some(piece::PieceEnum::QUEEN),
+        'K' => Some(piece::PieceEnum::KING),
+        _ => None,
+    }
+}
+
+fn letter_to_promotion(letter: char) -> Option<piece::PromotionPiece> {
+    match letter {
+        'R' => Some(piece::PromotionPiece::Rook),
+        'N' => Some(piece::PromotionPiece::Knight),
+        'B' => Some(piece::PromotionPiece::Bishop),
+        'Q' => Some(piece::PromotionPiece::Queen),
+        _ => None,
+    }
+}
+
+fn piece_at(game: &game::Game, square: (u8, u8)) -> Option<piece::PieceEnum> {
+    game.board()
+        .get_square(position::Position(square.0, square.1))
+        .as_ref()
+        .map(|p| p.piece())
+}
+
+// the move has to be legal in the given game
+pub(crate) fn format(game: &game::Game, mv: &chessmove::ChessMove) -> String {
+    let piece = match piece_at(game, mv.from) {
+        None => panic!("No piece at position: {:?}", mv.from),
+        Some(piece) => piece,
+    };
+
+    let mut san = String::new();
+
+    if piece == piece::PieceEnum::KING && (mv.from.0 as i8 - mv.to.0 as i8).abs() == 2 {
+        if mv.to.0 > mv.from.0 {
+            san.push_str("O-O");
+        } else {
+            san.push_str("O-O-O");
+        }
+    } else {
+        let is_capture = piece_at(game, mv.to).is_some()
+            || (piece == piece::PieceEnum::PAWN && mv.from.0 != mv.to.0);
+
+        match piece_letter(piece) {
+            None => {
+                if is_capture {
+                    san.push((b'a' + mv.from.0 - 1) as char);
+                }
+            }
+            Some(letter) => {
+                san.push(letter);
+                san.push_str(&disambiguation(game, mv, piece));
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+
+        san.push_str(&fen::square_name(position::Position(mv.to.0, mv.to.1)));
+
+        if let Some(promotion) = mv.promotion {
+            san.push('=');
+            san.push(promotion_letter(promotion));
+        }
+    }
+
+    if game.gives_check(mv) {
+        let mut after_move = game.copy_position();
+        after_move.make_move(*mv);
+        if after_move.legal_moves().is_empty() {
+            san.push('#');
+        } else {
+            san.push('+');
+        }
+    }
+
+    san
+}
+
+fn disambiguation(game: &game::Game, mv: &chessmove::ChessMove, piece: piece::PieceEnum) -> String {
+    let others = game
+        .legal_moves()
+        .into_iter()
+        .filter(|other| {
+            other.to == mv.to && other.from != mv.from && piece_at(game, other.from) == Some(piece)
+        })
+        .collect::<Vec<_>>();
+
+    if others.is_empty() {
+        String::new()
+    } else if others.iter().all(|other| other.from.0 != mv.from.0) {
+        ((b'a' + mv.from.0 - 1) as char).to_string()
+    } else if others.iter().all(|other| other.from.1 != mv.from.1) {
+        ((b'1' + mv.from.1 - 1) as char).to_string()
+    } else {
+        fen::square_name(position::Position(mv.from.0, mv.from.1))
+    }
+}
+
+pub(crate) fn parse(game: &game::Game, san: &str) -> Result<chessmove::ChessMove, SanError> {
+    let invalid = || SanError::InvalidSan(san.to_string());
+
+    let mut stripped = san.trim();
+    stripped = stripped.strip_suffix("e.p.").unwrap_or(stripped).trim_end();
+    let stripped = stripped.trim_end_matches(|c| ['+', '#', '!', '?'].contains(&c));
+
+    let legal_moves = game.legal_moves();
+
+    let candidates = match stripped {
+        "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+            let king = game.current_king_position();
+            let to_file = if stripped.len() == 3 {
+                king.0 + 2
+            } else {
+                king.0.wrapping_sub(2)
+            };
+            legal_moves
+                .into_iter()
+                .filter(|mv| {
+                    mv.from == (king.0, king.1)
+                        && mv.to == (to_file, king.1)
+                        && piece_at(game, mv.from) == Some(piece::PieceEnum::KING)
+                })
+                .collect::<Vec<_>>()
+        }
+        _ => {
+            let mut chars = stripped.chars().collect::<Vec<_>>();
+
+            let piece = match chars.first().and_then(|c| letter_to_piece(*c)) {
+                Some(piece) => {
+                    chars.remove(0);
+                    piece
+                }
+                None => piece::PieceEnum::PAWN,
+            };
+
+            let mut promotion = None;
+            if piece == piece::PieceEnum::PAWN {
+                if let Some(p) = chars.last().and_then(|c| letter_to_promotion(*c)) {
+                    promotion = Some(p);
+                    chars.pop();
+                    if chars.last() == Some(&'=') {
+                        chars.pop();
+                    }
+                }
+            }
+
+            if chars.len() < 2 {
+                return Err(invalid());
+            }
+            let to_str = chars
+                .split_off(chars.len() - 2)
+                .into_iter()
+                .collect::<String>();
+            let to = fen::parse_square(&to_str).ok_or_else(invalid)?;
+
+            if chars.last() == Some(&'x') || chars.last() == Some(&':') {
+                chars.pop();
+            }
+
+            let mut from_file = None;
+            let mut from_rank = None;
+            for c in chars {
+                match c {
+                    'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                        from_file = Some(c as u8 - b'a' + 1)
+                    }
+                    '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1' + 1),
+                    _ => return Err(invalid()),
+                }
+            }
+
+            if piece == piece::PieceEnum::PAWN && from_file.is_none() {
+                from_file = Some(to.0);
+            }
+
+            legal_moves
+                .into_iter()
+                .filter(|mv| {
+                    mv.to == (to.0, to.1)
+                        && mv.promotion == promotion
+                        && piece_at(game, mv.from) == Some(piece)
+                        && (from_file.is_none() || from_file == Some(mv.from.0))
+                        && (from_rank.is_none() || from_rank == Some(mv.from.1))
+                        && !(piece == piece::PieceEnum::KING
+                            && (mv.from.0 as i8 - mv.to.0 as i8).abs() == 2)
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+
+    match candidates.len() {
+        0 => Err(SanError::IllegalMove(san.to_string())),
+        1 => Ok(candidates[0]),
+        _ => Err(SanError::AmbiguousMove(san.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mv(from: (u8, u8), to: (u8, u8)) -> chessmove::ChessMove {
+        chessmove::ChessMove {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+
+    #[test]
+    fn pawn_and_piece_moves() {
+        let game = game::Game::new();
+        assert_eq!("e4", game.move_to_san(&mv((5, 2), (5, 4))));
+        assert_eq!("Nf3", game.move_to_san(&mv((7, 1), (6, 3))));
+        assert_eq!(Ok(mv((5, 2), (5, 4))), game.parse_san("e4"));
+        assert_eq!(Ok(mv((7, 1), (6, 3))), game.parse_san("Nf3"));
+    }
+
+    #[test]
+    fn file_disambiguation() {
+        let game = game::Game::from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!("Nbd2", game.move_to_san(&mv((2, 1), (4, 2))));
+        assert_eq!("Nfd2", game.move_to_san(&mv((6, 3), (4, 2))));
+        assert_eq!(Ok(mv((2, 1), (4, 2))), game.parse_san("Nbd2"));
+        assert_eq!(
+            Err(SanError::AmbiguousMove("Nd2".to_string())),
+            game.parse_san("Nd2")
+        );
+    }
+
+    #[test]
+    fn rank_and_square_disambiguation() {
+        let game = game::Game::from_fen("1k6/8/1K6/N7/4Q2Q/8/8/N6Q w - - 0 1").unwrap();
+        assert_eq!("N1b3", game.move_to_san(&mv((1, 1), (2, 3))));
+        assert_eq!("Qh4e1", game.move_to_san(&mv((8, 4), (5, 1))));
+        assert_eq!("Qee1", game.move_to_san(&mv((5, 4), (5, 1))));
+        assert_eq!("Q1e1", game.move_to_san(&mv((8, 1), (5, 1))));
+        assert_eq!(Ok(mv((8, 4), (5, 1))), game.parse_san("Qh4e1"));
+        assert_eq!(Ok(mv((1, 5), (2, 3))), game.parse_san("N5b3"));
+    }
+
+    #[test]
+    fn en_passant_capture() {
+        let game = game::Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!("exd6", game.move_to_san(&mv((5, 5), (4, 6))));
+        assert_eq!(Ok(mv((5, 5), (4, 6))), game.parse_san("exd6 e.p."));
+        assert_eq!(Ok(mv((5, 5), (4, 6))), game.parse_san("exd6"));
+    }
+
+    #[test]
+    fn castling() {
+        let game = game::Game::from_fen("r3k2r/8/8/8/8/8/8/3K4 b kq - 0 1").unwrap();
+        assert_eq!("O-O-O+", game.move_to_san(&mv((5, 8), (3, 8))));
+        assert_eq!("O-O", game.move_to_san(&mv((5, 8), (7, 8))));
+        assert_eq!(Ok(mv((5, 8), (3, 8))), game.parse_san("O-O-O+"));
+        assert_eq!(Ok(mv((5, 8), (7, 8))), game.parse_san("0-0"));
+    }
+
+    #[test]
+    fn promotion_with_mate() {
+        let game = game::Game::from_fen("k7/4P3/1K6/8/8/8/8/8 w - - 0 1").unwrap();
+        let promotion = |promotion| chessmove::ChessMove {
+            from: (5, 7),
+            to: (5, 8),
+            promotion: Some(promotion),
+        };
+        assert_eq!(
+            "e8=Q#",
+            game.move_to_san(&promotion(piece::PromotionPiece::Queen))
+        );
+        assert_eq!(
+            "e8=N",
+            game.move_to_san(&promotion(piece::PromotionPiece::Knight))
+        );
+        assert_eq!(
+            Ok(promotion(piece::PromotionPiece::Queen)),
+            game.parse_san("e8=Q#")
+        );
+        assert_eq!(
+            Ok(promotion(piece::PromotionPiece::Rook)),
+            game.parse_san("e8R")
+        );
+    }
+
+    #[test]
+    fn captures_and_check() {
+        let game = game::Game::from_fen("4k3/8/8/3p4/4P3/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!("exd5", game.move_to_san(&mv((5, 4), (4, 5))));
+        assert_eq!("Ra8+", game.move_to_san(&mv((1, 1), (1, 8))));
+        assert_eq!(Ok(mv((5, 4), (4, 5))), game.parse_san("exd5"));
+    }
+
+    #[test]
+    fn invalid_and_illegal() {
+        let game = game::Game::new();
+        assert_eq!(
+            Err(SanError::IllegalMove("e5".to_string())),
+            game.parse_san("e5")
+        );
+        assert_eq!(
+            Err(SanError::IllegalMove("O-O".to_string())),
+            game.parse_san("O-O")
+        );
+        assert_eq!(
+            Err(SanError::InvalidSan("Nz9".to_string())),
+            game.parse_san("Nz9")
+        );
+        assert_eq!(
+            Err(SanError::InvalidSan("".to_string())),
+            game.parse_san("")
+        );
+    }
+}
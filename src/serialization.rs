@@ -0,0 +1,151 @@
+// Serialize and Deserialize for the types that are not derived with the serde feature.
+//
+// Games serialise as their FEN and boards as the piece placement field of it. For a form with
+// named fields, put `#[serde(with = "legal_chess::serialization::structured_game")]` or
+// `structured_board` on the field. Either way positions are validated when deserialised and
+// the move history of a game is not kept. Moves and squares serialise as UCI moves and
+// square names such as "e7e8q" and "e8".
+use crate::pieces::{piece, position};
+use crate::{board, chessmove, fen, game, square};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl Serialize for game::Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_fen())
+    }
+}
+
+impl<'de> Deserialize<'de> for game::Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        game::Game::from_fen(&fen).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for board::Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&fen::placement(self))
+    }
+}
+
+impl<'de> Deserialize<'de> for board::Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let placement = String::deserialize(deserializer)?;
+        let u8_board = fen::parse_placement(&placement).map_err(D::Error::custom)?;
+        let (board, _, _) = board::Board::try_from_u8_board(&u8_board).map_err(D::Error::custom)?;
+        Ok(board)
+    }
+}
+
+impl Serialize for chessmove::ChessMove {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for chessmove::ChessMove {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let uci = String::deserialize(deserializer)?;
+        uci.parse().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for square::Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for square::Square {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(D::Error::custom)
+    }
+}
+
+type Pieces = std::collections::BTreeMap<square::Square, piece::ColoredPiece>;
+
+fn pieces(board: &board::Board) -> Pieces {
+    square::Square::all()
+        .filter_map(|square| board.piece_on(square).map(|piece| (square, piece)))
+        .collect()
+}
+
+fn board_from_pieces(pieces: &Pieces) -> board::Board {
+    let mut board = board::Board::empty();
+    for (square, piece) in pieces {
+        board.set_piece((*square).into(), Some(*piece));
+    }
+    board
+}
+
+pub mod structured_board {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        board: &board::Board,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        pieces(board).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<board::Board, D::Error> {
+        let board = board_from_pieces(&Pieces::deserialize(deserializer)?);
+        fen::parse_placement(&fen::placement(&board)).map_err(D::Error::custom)?;
+        Ok(board)
+    }
+}
+
+pub mod structured_game {
+    use super::*;
+    use crate::color;
+    use std::convert::TryFrom;
+
+    #[derive(Serialize, Deserialize)]
+    struct Structured {
+        pieces: Pieces,
+        side_to_move: color::Color,
+        // kingside and queenside
+        castling_rights_white: (bool, bool),
+        castling_rights_black: (bool, bool),
+        en_passant: Option<square::Square>,
+        half_moves: u16,
+        full_moves: u16,
+    }
+
+    pub fn serialize<S: Serializer>(game: &game::Game, serializer: S) -> Result<S::Ok, S::Error> {
+        Structured {
+            pieces: pieces(game.board()),
+            side_to_move: *game.side_to_move(),
+            castling_rights_white: game.castling_rights_white(),
+            castling_rights_black: game.castling_rights_black(),
+            en_passant: game
+                .en_passant()
+                .and_then(|ep| square::Square::try_from(ep).ok()),
+            half_moves: game.half_moves(),
+            full_moves: game.full_moves(),
+        }
+        .serialize(serializer)
+    }
+
+    // goes through the FEN of the position, which validates it
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<game::Game, D::Error> {
+        let structured = Structured::deserialize(deserializer)?;
+        let fen = format!(
+            "{} {} {} {} {} {}",
+            fen::placement(&board_from_pieces(&structured.pieces)),
+            fen::side_to_move(structured.side_to_move),
+            fen::castling(
+                structured.castling_rights_white,
+                structured.castling_rights_black
+            ),
+            fen::en_passant(structured.en_passant.map(position::Position::from)),
+            structured.half_moves,
+            structured.full_moves
+        );
+        game::Game::from_fen(&fen).map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::fen;
+use crate::pieces::position;
+use std::convert::TryFrom;
+use std::{error, fmt, str};
+
+// One of the 64 squares of the board, a1 is 0, b1 is 1 and h8 is 63. Unlike `Position` and the
+// tuples of `ChessMove`, a square is always on the board.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct Square(u8);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SquareError {
+    InvalidName(String),
+    // file and rank of a 1-based position or tuple
+    OutOfRange(u8, u8),
+}
+
+impl fmt::Display for SquareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SquareError::InvalidName(s) => write!(f, "'{}' is not a square", s),
+            SquareError::OutOfRange(file, rank) => {
+                write!(f, "file {} and rank {} are not on the board", file, rank)
+            }
+        }
+    }
+}
+
+impl error::Error for SquareError {}
+
+impl Square {
+    pub fn new(index: u8) -> Option<Self> {
+        if index < 64 {
+            Some(Square(index))
+        } else {
+            None
+        }
+    }
+
+    // file and rank count from zero, so a1 is (0, 0)
+    pub fn from_file_rank(file: u8, rank: u8) -> Option<Self> {
+        if file < 8 && rank < 8 {
+            Some(Square(rank * 8 + file))
+        } else {
+            None
+        }
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    pub fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    // the square the given number of files and ranks away, if it is still on the board
+    pub fn offset(self, files: i8, ranks: i8) -> Option<Self> {
+        let file = self.file() as i8 + files;
+        let rank = self.rank() as i8 + ranks;
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Self::from_file_rank(file as u8, rank as u8)
+        } else {
+            None
+        }
+    }
+
+    // a1, b1, ..., h1, a2, ..., h8
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..64).map(Square)
+    }
+}
+
+impl From<Square> for position::Position {
+    fn from(square: Square) -> Self {
+        position::Position(square.file() + 1, square.rank() + 1)
+    }
+}
+
+impl TryFrom<position::Position> for Square {
+    type Error = SquareError;
+
+    fn try_from(position: position::Position) -> Result<Self, Self::Error> {
+        Square::try_from((position.0, position.1))
+    }
+}
+
+// the 1-based (file, rank) tuples used by `ChessMove`
+impl From<Square> for (u8, u8) {
+    fn from(square: Square) -> Self {
+        (square.file() + 1, square.rank() + 1)
+    }
+}
+
+impl TryFrom<(u8, u8)> for Square {
+    type Error = SquareError;
+
+    fn try_from((file, rank): (u8, u8)) -> Result<Self, Self::Error> {
+        match (file.checked_sub(1), rank.checked_sub(1)) {
+            (Some(f), Some(r)) => Square::from_file_rank(f, r),
+            _ => None,
+        }
+        .ok_or(SquareError::OutOfRange(file, rank))
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", fen::square_name((*self).into()))
+    }
+}
+
+impl str::FromStr for Square {
+    type Err = SquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match fen::parse_square(s) {
+            Some(position) => Square::try_from(position),
+            None => Err(SquareError::InvalidName(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_coordinates() {
+        let e4: Square = "e4".parse().unwrap();
+        assert_eq!(28, e4.index());
+        assert_eq!((4, 3), (e4.file(), e4.rank()));
+        assert_eq!("e4", e4.to_string());
+        assert_eq!(Some(e4), Square::from_file_rank(4, 3));
+
+        assert_eq!(
+            Err(SquareError::InvalidName("i1".to_string())),
+            "i1".parse::<Square>()
+        );
+        assert!("e".parse::<Square>().is_err());
+        assert!("e44".parse::<Square>().is_err());
+        assert_eq!(None, Square::new(64));
+        assert_eq!(None, Square::from_file_rank(8, 0));
+    }
+
+    #[test]
+    fn offsets() {
+        let b1: Square = "b1".parse().unwrap();
+        assert_eq!(Some("c3".parse().unwrap()), b1.offset(1, 2));
+        assert_eq!(Some("a1".parse().unwrap()), b1.offset(-1, 0));
+        assert_eq!(None, b1.offset(-2, 1));
+        assert_eq!(None, b1.offset(0, -1));
+        assert_eq!(None, b1.offset(0, 8));
+    }
+
+    #[test]
+    fn conversions_are_lossless() {
+        let squares = Square::all().collect::<Vec<_>>();
+        assert_eq!(64, squares.len());
+        assert_eq!("h8", squares[63].to_string());
+
+        for square in squares {
+            let position: position::Position = square.into();
+            assert_eq!(Ok(square), Square::try_from(position));
+            assert_eq!(position.bit(), 1 << square.index());
+
+            let tuple: (u8, u8) = square.into();
+            assert_eq!(Ok(square), Square::try_from(tuple));
+            assert_eq!(Ok(square), square.to_string().parse());
+        }
+
+        assert_eq!(
+            Err(SquareError::OutOfRange(0, 0)),
+            Square::try_from(position::Position(0, 0))
+        );
+        assert_eq!(Err(SquareError::OutOfRange(9, 1)), Square::try_from((9, 1)));
+    }
+}
@@ -0,0 +1,394 @@
+use crate::pieces::piece;
+use crate::{board, chessmove, color, game, square};
+use std::convert::TryFrom;
+use std::fmt;
+
+const SQUARE_SIZE: f32 = 45.0;
+const COORDINATES_MARGIN: f32 = 20.0;
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+
+// Glyphs drawn on a square of 45 units. They take their fill from where they are used, the
+// details that have to stand out against the fill use `currentColor`.
+const GLYPHS: [(&str, &str); 6] = [
+    (
+        "king",
+        r##"<path d="M19 24l1-9h5l1 9z"/><path d="M11 35c-5-10 2-18 11.5-11c9.5-7 16.5 1 11.5 11z"/><rect x="10" y="35" width="25" height="4"/><path d="M22.5 4v9M18.5 8h8" fill="none" stroke-width="2"/>"##,
+    ),
+    (
+        "queen",
+        r##"<path d="M10 35l-3-21l6 11l2-14l5 13l2.5-15l2.5 15l5-13l2 14l6-11l-3 21z"/><circle cx="7" cy="12" r="2"/><circle cx="15" cy="9" r="2"/><circle cx="22.5" cy="7" r="2"/><circle cx="30" cy="9" r="2"/><circle cx="38" cy="12" r="2"/><rect x="10" y="35" width="25" height="4"/>"##,
+    ),
+    (
+        "rook",
+        r##"<path d="M13 35l2-19h15l2 19z"/><path d="M11 9h4v3h5v-3h5v3h5v-3h4v7h-23z"/><rect x="9" y="35" width="27" height="4"/>"##,
+    ),
+    (
+        "bishop",
+        r##"<path d="M15 35c-3-8 2-18 7.5-23c5.5 5 10.5 15 7.5 23z"/><circle cx="22.5" cy="9" r="3"/><path d="M20 20l5 5" fill="none" stroke="currentColor"/><rect x="10" y="35" width="25" height="4"/>"##,
+    ),
+    (
+        "knight",
+        r##"<path d="M12 39h22c0-11-3-22-10-28l-2-5l-3 4c-5 2-10 10-10 15c0 3 3 4 5 2c2-2 5-3 7-4c-2 6-8 9-9 16z"/><circle cx="17" cy="17" r="1.2" fill="currentColor" stroke="none"/>"##,
+    ),
+    (
+        "pawn",
+        r##"<circle cx="22.5" cy="14" r="5"/><path d="M17 21h11l-2 5l3 9h-13l3-9z"/><rect x="12" y="35" width="21" height="4"/>"##,
+    ),
+];
+
+// a mark drawn on top of the pieces, the colour is any SVG colour such as "green" or "#15781b"
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Annotation {
+    Arrow {
+        from: square::Square,
+        to: square::Square,
+        color: String,
+    },
+    Circle {
+        square: square::Square,
+        color: String,
+    },
+}
+
+// Renders a board as a self-contained SVG diagram. Use `to_string()` or `write!` to get the text.
+pub struct SvgBoard<'a> {
+    board: &'a board::Board,
+    flipped: bool,
+    coordinates: bool,
+    last_move: Option<chessmove::ChessMove>,
+    check: Option<square::Square>,
+    annotations: Vec<Annotation>,
+}
+
+impl<'a> SvgBoard<'a> {
+    pub fn new(board: &'a board::Board) -> Self {
+        Self {
+            board,
+            flipped: false,
+            coordinates: true,
+            last_move: None,
+            check: None,
+            annotations: vec![],
+        }
+    }
+
+    // the board of the game with its last move and, if there is one, the check highlighted
+    pub fn from_game(game: &'a game::Game) -> Self {
+        let mut svg = Self::new(game.board());
+        svg.last_move = game.move_history().last().copied();
+        if game.is_check() {
+            svg.check = square::Square::try_from(game.current_king_position()).ok();
+        }
+        svg
+    }
+
+    // draws the board from Black's side
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    pub fn coordinates(mut self, coordinates: bool) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    pub fn last_move(mut self, mv: chessmove::ChessMove) -> Self {
+        self.last_move = Some(mv);
+        self
+    }
+
+    // the square of the king in check
+    pub fn check(mut self, square: square::Square) -> Self {
+        self.check = Some(square);
+        self
+    }
+
+    pub fn annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    fn margin(&self) -> f32 {
+        if self.coordinates {
+            COORDINATES_MARGIN
+        } else {
+            0.0
+        }
+    }
+
+    // the top left corner of the square in the picture
+    fn corner(&self, square: square::Square) -> (f32, f32) {
+        let (column, row) = match self.flipped {
+            false => (square.file(), 7 - square.rank()),
+            true => (7 - square.file(), square.rank()),
+        };
+        (
+            self.margin() + column as f32 * SQUARE_SIZE,
+            row as f32 * SQUARE_SIZE,
+        )
+    }
+
+    fn center(&self, square: square::Square) -> (f32, f32) {
+        let (x, y) = self.corner(square);
+        (x + SQUARE_SIZE / 2.0, y + SQUARE_SIZE / 2.0)
+    }
+
+    fn write_squares(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highlighted = self
+            .last_move
+            .iter()
+            .flat_map(|mv| vec![mv.from, mv.to])
+            .filter_map(|tuple| square::Square::try_from(tuple).ok())
+            .collect::<Vec<_>>();
+
+        for square in square::Square::all() {
+            let (x, y) = self.corner(square);
+            let color = match (square.file() + square.rank()) % 2 {
+                0 => DARK_SQUARE,
+                _ => LIGHT_SQUARE,
+            };
+            write!(
+                f,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                x, y, SQUARE_SIZE, SQUARE_SIZE, color
+            )?;
+            if highlighted.contains(&square) {
+                write!(
+                    f,
+                    r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#ffff33" fill-opacity="0.4"/>"##,
+                    x, y, SQUARE_SIZE, SQUARE_SIZE
+                )?;
+            }
+            if self.check == Some(square) {
+                write!(
+                    f,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="url(#check)"/>"#,
+                    x, y, SQUARE_SIZE, SQUARE_SIZE
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_coordinates(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // the squares a1, b2, ..., h8 lie on every file and every rank once
+        for i in 0..8 {
+            let square = match square::Square::from_file_rank(i, i) {
+                Some(square) => square,
+                None => continue,
+            };
+            let (x, y) = self.center(square);
+            write!(
+                f,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                COORDINATES_MARGIN / 2.0,
+                y + 4.0,
+                i + 1
+            )?;
+            write!(
+                f,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                x,
+                8.0 * SQUARE_SIZE + 14.0,
+                (b'a' + i) as char
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_pieces(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for square in square::Square::all() {
+            let piece = match self.board.piece_on(square) {
+                Some(piece) => piece,
+                None => continue,
+            };
+            let (fill, detail) = match piece.color {
+                color::Color::WHITE => ("#fff", "#000"),
+                color::Color::BLACK => ("#000", "#fff"),
+            };
+            let (x, y) = self.corner(square);
+            write!(
+                f,
+                r##"<use xlink:href="#{}" x="{}" y="{}" fill="{}" color="{}"/>"##,
+                glyph_name(piece.piece),
+                x,
+                y,
+                fill,
+                detail
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_annotations(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for annotation in &self.annotations {
+            match annotation {
+                Annotation::Circle { square, color } => {
+                    let (x, y) = self.center(*square);
+                    write!(
+                        f,
+                        r#"<circle cx="{}" cy="{}" r="20" fill="none" stroke="{}" stroke-width="3" stroke-opacity="0.8"/>"#,
+                        x,
+                        y,
+                        escape(color)
+                    )?;
+                }
+                Annotation::Arrow { from, to, color } => {
+                    let (x1, y1) = self.center(*from);
+                    let (x2, y2) = self.center(*to);
+                    let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+                    if length == 0.0 {
+                        continue;
+                    }
+                    // the shaft stops where the head begins, the head is a triangle at the tip
+                    let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+                    let (head_length, head_width) = (15.0, 8.0);
+                    let (bx, by) = (x2 - dx * head_length, y2 - dy * head_length);
+                    write!(
+                        f,
+                        r#"<g fill="{color}" stroke="{color}" opacity="0.8"><line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke-width="7"/><polygon points="{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}" stroke="none"/></g>"#,
+                        x1,
+                        y1,
+                        bx,
+                        by,
+                        x2,
+                        y2,
+                        bx - dy * head_width,
+                        by + dx * head_width,
+                        bx + dy * head_width,
+                        by - dx * head_width,
+                        color = escape(color)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for SvgBoard<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size = self.margin() + 8.0 * SQUARE_SIZE;
+        write!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" viewBox="0 0 {} {}" width="{}" height="{}">"#,
+            size, size, size, size
+        )?;
+        f.write_str(r##"<defs><radialGradient id="check"><stop offset="0%" stop-color="#ff0000"/><stop offset="50%" stop-color="#e70000"/><stop offset="100%" stop-color="#ff0000" stop-opacity="0"/></radialGradient>"##)?;
+        for (name, glyph) in GLYPHS.iter() {
+            write!(
+                f,
+                r##"<g id="{}" stroke="#000" stroke-width="1.5" stroke-linejoin="round">{}</g>"##,
+                name, glyph
+            )?;
+        }
+        f.write_str("</defs>")?;
+
+        self.write_squares(f)?;
+        if self.coordinates {
+            f.write_str(r#"<g font-family="sans-serif" font-size="12" text-anchor="middle">"#)?;
+            self.write_coordinates(f)?;
+            f.write_str("</g>")?;
+        }
+        self.write_pieces(f)?;
+        self.write_annotations(f)?;
+        f.write_str("</svg>")
+    }
+}
+
+fn glyph_name(piece: piece::PieceEnum) -> &'static str {
+    match piece {
+        piece::PieceEnum::KING => "king",
+        piece::PieceEnum::QUEEN => "queen",
+        piece::PieceEnum::ROOK => "rook",
+        piece::PieceEnum::BISHOP => "bishop",
+        piece::PieceEnum::KNIGHT => "knight",
+        piece::PieceEnum::PAWN => "pawn",
+    }
+}
+
+// keeps colours given by the caller from breaking out of their attribute
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> square::Square {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn initial_board() {
+        let board = board::Board::initial();
+        let svg = SvgBoard::new(&board).to_string();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        // SVG 1.1 renderers such as librsvg only resolve `xlink:href`
+        assert!(svg.contains("xmlns:xlink=\"http://www.w3.org/1999/xlink\""));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("viewBox=\"0 0 380 380\""));
+        assert_eq!(32, svg.matches("<use ").count());
+        assert_eq!(32, svg.matches(DARK_SQUARE).count());
+        assert_eq!(16, svg.matches("<text ").count());
+        assert!(svg.contains(r##"<use xlink:href="#rook" x="20" y="315" fill="#fff""##));
+        assert!(svg.contains(r##"<use xlink:href="#king" x="200" y="0" fill="#000""##));
+        assert!(!svg.contains("url(#check)\"/>"));
+    }
+
+    #[test]
+    fn flipped_without_coordinates() {
+        let board = board::Board::initial();
+        let svg = SvgBoard::new(&board)
+            .flipped(true)
+            .coordinates(false)
+            .to_string();
+        assert!(svg.contains("viewBox=\"0 0 360 360\""));
+        assert!(!svg.contains("<text "));
+        assert!(svg.contains(r##"<use xlink:href="#rook" x="315" y="0" fill="#fff""##));
+        assert!(svg.contains(r##"<use xlink:href="#king" x="135" y="315" fill="#000""##));
+    }
+
+    #[test]
+    fn last_move_and_check_of_a_game() {
+        let mut game = game::Game::new();
+        game::play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+        let svg = SvgBoard::from_game(&game).to_string();
+        assert_eq!(2, svg.matches("fill-opacity=\"0.4\"").count());
+        assert!(svg.contains(r##"<rect x="335" y="180" width="45" height="45" fill="#ffff33""##));
+        assert!(
+            svg.contains(r#"<rect x="200" y="315" width="45" height="45" fill="url(#check)"/>"#)
+        );
+
+        game.undo_last_move();
+        let svg = SvgBoard::from_game(&game).to_string();
+        assert!(!svg.contains("fill=\"url(#check)\""));
+    }
+
+    #[test]
+    fn annotations() {
+        let board = board::Board::initial();
+        let svg = SvgBoard::new(&board)
+            .annotation(Annotation::Arrow {
+                from: square("e2"),
+                to: square("e4"),
+                color: "green".to_string(),
+            })
+            .annotation(Annotation::Circle {
+                square: square("d5"),
+                color: "red\"/><script>".to_string(),
+            })
+            .to_string();
+        assert!(svg.contains(
+            r#"<line x1="222.5" y1="292.5" x2="222.5" y2="217.5" stroke-width="7"/><polygon points="222.5,202.5 230.5,217.5 214.5,217.5""#
+        ));
+        assert!(svg.contains(r#"<circle cx="177.5" cy="157.5" r="20""#));
+        assert!(svg.contains("stroke=\"red&quot;/>&lt;script>\""));
+        assert!(!svg.contains("<script>"));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::color;
+use crate::pieces::{piece, position};
+
+// 12 pieces on 64 squares, the side to move, 4 castling rights and 8 en passant files
+const PIECE_KEYS: usize = 0;
+const SIDE_KEY: usize = 12 * 64;
+const CASTLING_KEYS: usize = SIDE_KEY + 1;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 4;
+const KEY_COUNT: usize = EN_PASSANT_KEYS + 8;
+
+// the keys are generated at compile time from a fixed seed so hashes can be persisted
+const KEYS: [u64; KEY_COUNT] = generate_keys(0x5eed_c0ff_ee15_900d);
+
+// splitmix64
+const fn generate_keys(seed: u64) -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state = seed;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+pub(crate) fn piece(piece: piece::ColoredPiece, position: position::Position) -> u64 {
+    let kind = match piece.piece {
+        piece::PieceEnum::PAWN => 0,
+        piece::PieceEnum::KNIGHT => 1,
+        piece::PieceEnum::BISHOP => 2,
+        piece::PieceEnum::ROOK => 3,
+        piece::PieceEnum::QUEEN => 4,
+        piece::PieceEnum::KING => 5,
+    };
+    let color = match piece.color {
+        color::Color::WHITE => 0,
+        color::Color::BLACK => 6,
+    };
+    let square = (position.1 as usize - 1) * 8 + position.0 as usize - 1;
+    KEYS[PIECE_KEYS + (color + kind) * 64 + square]
+}
+
+// xored in while black is to move
+pub(crate) fn side(side_to_move: color::Color) -> u64 {
+    match side_to_move {
+        color::Color::WHITE => 0,
+        color::Color::BLACK => KEYS[SIDE_KEY],
+    }
+}
+
+pub(crate) fn castling(white: (bool, bool), black: (bool, bool)) -> u64 {
+    [white.0, white.1, black.0, black.1]
+        .iter()
+        .enumerate()
+        .filter(|(_, right)| **right)
+        .fold(0, |key, (i, _)| key ^ KEYS[CASTLING_KEYS + i])
+}
+
+pub(crate) fn en_passant(en_passant: Option<position::Position>) -> u64 {
+    match en_passant {
+        None => 0,
+        Some(ep) => KEYS[EN_PASSANT_KEYS + ep.0 as usize - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_distinct_and_non_zero() {
+        let mut keys = KEYS.to_vec();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(KEY_COUNT, keys.len());
+        assert!(keys.iter().all(|key| *key != 0));
+    }
+}
@@ -0,0 +1,57 @@
+extern crate legal_chess;
+
+use legal_chess::{chessmove, game};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// counts every allocation made by this test binary
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const POSITIONS: [&str; 5] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/8/8/KPp4r/8/8/8/7k w - c6 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "4k3/8/8/8/8/5n2/8/r3K3 w - - 0 1",
+];
+
+// the only test in this binary, so no other thread allocates while it counts
+#[test]
+fn generate_legal_moves_does_not_allocate() {
+    let games = POSITIONS
+        .iter()
+        .map(|fen| game::Game::from_fen(fen).unwrap())
+        .collect::<Vec<_>>();
+    let mut moves = chessmove::MoveList::new();
+
+    // lookup tables may be built on first use
+    games[0].generate_legal_moves(&mut moves);
+
+    for game in &games {
+        let before = ALLOCATIONS.load(Ordering::SeqCst);
+        game.generate_legal_moves(&mut moves);
+        assert_eq!(
+            before,
+            ALLOCATIONS.load(Ordering::SeqCst),
+            "{}",
+            game.to_fen()
+        );
+        assert_eq!(game.legal_moves(), moves.to_vec());
+    }
+}
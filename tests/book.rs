@@ -0,0 +1,78 @@
+extern crate legal_chess;
+
+use legal_chess::book::reader::{BookError, PolyglotBook};
+use legal_chess::game;
+
+const SMALL_BOOK: &str = "tests/fixtures/small.bin";
+
+fn book_moves(book: &PolyglotBook, game: &game::Game) -> Vec<(String, u16)> {
+    book.entries(game)
+        .iter()
+        .map(|entry| (entry.chessmove.to_string(), entry.weight))
+        .collect()
+}
+
+#[test]
+fn entries_of_initial_position() {
+    let book = PolyglotBook::open(SMALL_BOOK).unwrap();
+    assert_eq!(6, book.len());
+    assert_eq!(
+        vec![
+            ("e2e4".to_string(), 30),
+            ("d2d4".to_string(), 20),
+            ("g1f3".to_string(), 10)
+        ],
+        book_moves(&book, &game::Game::new())
+    );
+}
+
+#[test]
+fn entries_after_moves() {
+    let book = PolyglotBook::open(SMALL_BOOK).unwrap();
+    let mut game = game::Game::new();
+    game.make_move(game.parse_san("e4").unwrap());
+    assert_eq!(
+        vec![("c7c5".to_string(), 5), ("e7e5".to_string(), 5)],
+        book_moves(&book, &game)
+    );
+
+    for san in &["e5", "Nf3", "Nc6", "Bc4", "Nf6"] {
+        game.make_move(game.parse_san(san).unwrap());
+    }
+    assert_eq!(vec![("e1g1".to_string(), 1)], book_moves(&book, &game));
+
+    game.make_move(game.parse_san("O-O").unwrap());
+    assert!(book.entries(&game).is_empty());
+}
+
+#[test]
+fn weighted_pick() {
+    let book = PolyglotBook::open(SMALL_BOOK).unwrap();
+    let game = game::Game::new();
+    let pick = |random| {
+        book.weighted_pick(&game, random)
+            .unwrap()
+            .chessmove
+            .to_string()
+    };
+    assert_eq!("e2e4", pick(0));
+    assert_eq!("e2e4", pick(29));
+    assert_eq!("d2d4", pick(30));
+    assert_eq!("g1f3", pick(59));
+    assert_eq!("e2e4", pick(60));
+
+    let mut game = game::Game::new();
+    game.make_move(game.parse_san("d4").unwrap());
+    assert_eq!(None, book.weighted_pick(&game, 0));
+}
+
+#[test]
+fn rejects_truncated_books() {
+    let bytes = std::fs::read(SMALL_BOOK).unwrap();
+    assert!(PolyglotBook::read(&bytes[..]).is_ok());
+    assert_eq!(
+        Some(BookError::InvalidLength(40)),
+        PolyglotBook::read(&bytes[..40]).err()
+    );
+    assert!(PolyglotBook::open("tests/fixtures/missing.bin").is_err());
+}
@@ -0,0 +1,112 @@
+extern crate legal_chess;
+
+use legal_chess::game;
+
+const INITIAL_GAME_ARR: [u8; 73] = [
+    2, 1, 0, 0, 0, 0, 11, 12, 3, 1, 0, 0, 0, 0, 11, 13, 4, 1, 0, 0, 0, 0, 11, 14, 5, 1, 0, 0, 0, 0,
+    11, 15, 6, 1, 0, 0, 0, 0, 11, 16, 4, 1, 0, 0, 0, 0, 11, 14, 3, 1, 0, 0, 0, 0, 11, 13, 2, 1, 0,
+    0, 0, 0, 11, 12, 0, 0, 1, 1, 1, 1, 0, 1, 0,
+];
+
+const KIWIPETE_GAME_ARR: [u8; 73] = [
+    2, 1, 0, 0, 0, 14, 11, 12, 0, 1, 0, 11, 0, 13, 0, 0, 0, 1, 3, 0, 0, 0, 11, 0, 0, 4, 0, 0, 1, 0,
+    11, 0, 6, 4, 0, 1, 3, 11, 15, 16, 0, 1, 5, 0, 0, 13, 11, 0, 0, 1, 0, 0, 0, 11, 14, 0, 2, 1, 11,
+    0, 0, 0, 0, 12, 0, 0, 1, 1, 1, 1, 0, 1, 0,
+];
+
+#[rustfmt::skip]
+const PERFT_3_GAME_ARR: [u8; 73] = [
+    0, 0, 0, 0, 6, 0, 0, 0,
+    0, 0, 0, 2, 1, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 11, 0,
+    0, 0, 0, 0, 0, 11, 0, 0,
+    0, 1, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 11, 0, 0, 0, 0,
+    0, 1, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 16, 12, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 1, 0,
+];
+
+#[rustfmt::skip]
+const PERFT_4_GAME_ARR: [u8; 73] = [
+    2, 1, 15, 4, 13, 0, 1, 12,
+    0, 11, 0, 4, 1, 14, 11, 0,
+    0, 0, 0, 1, 0, 0, 11, 0,
+    5, 1, 0, 0, 0, 0, 11, 0,
+    0, 0, 0, 1, 0, 0, 0, 16,
+    2, 0, 3, 0, 0, 13, 11, 0,
+    6, 1, 0, 0, 0, 14, 11, 0,
+    0, 1, 0, 0, 0, 3, 11, 12,
+    0, 0, 0, 0, 1, 1, 0, 1, 0,
+];
+
+#[rustfmt::skip]
+const PERFT_5_GAME_ARR: [u8; 73] = [
+    2, 1, 0, 0, 0, 0, 11, 12,
+    3, 1, 0, 0, 0, 0, 11, 13,
+    4, 1, 0, 4, 0, 11, 0, 14,
+    5, 0, 0, 0, 0, 0, 1, 15,
+    6, 3, 0, 0, 0, 0, 14, 0,
+    0, 13, 0, 0, 0, 0, 11, 16,
+    0, 1, 0, 0, 0, 0, 11, 0,
+    2, 1, 0, 0, 0, 0, 11, 12,
+    0, 0, 1, 1, 0, 0, 1, 8, 0,
+];
+
+const PERFT_6_GAME_ARR: [u8; 73] = [
+    2, 0, 1, 0, 0, 11, 0, 12, 0, 1, 0, 0, 0, 0, 11, 0, 0, 1, 3, 4, 14, 13, 11, 0, 0, 0, 1, 0, 0,
+    11, 0, 0, 0, 5, 0, 1, 11, 0, 15, 0, 2, 1, 3, 0, 0, 13, 11, 12, 6, 1, 0, 14, 4, 0, 11, 16, 0, 1,
+    0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 10, 0,
+];
+
+const POSITIONS: [(&str, [u8; 73]); 6] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        INITIAL_GAME_ARR,
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        KIWIPETE_GAME_ARR,
+    ),
+    (
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        PERFT_3_GAME_ARR,
+    ),
+    (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        PERFT_4_GAME_ARR,
+    ),
+    (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        PERFT_5_GAME_ARR,
+    ),
+    (
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        PERFT_6_GAME_ARR,
+    ),
+];
+
+#[test]
+fn perft_positions_from_fen() {
+    for (fen, game_arr) in POSITIONS.iter() {
+        let game = game::Game::from_fen(fen).unwrap();
+        assert_eq!(game_arr[..], game.to_game_arr()[..], "{}", fen);
+    }
+}
+
+#[test]
+fn perft_positions_to_fen() {
+    for (fen, game_arr) in POSITIONS.iter() {
+        let game = game::Game::from_game_arr(game_arr);
+        assert_eq!(*fen, game.to_fen());
+    }
+}
+
+#[test]
+fn perft_positions_round_trip() {
+    for (_, game_arr) in POSITIONS.iter() {
+        let game = game::Game::from_game_arr(game_arr);
+        let round_tripped = game::Game::from_fen(&game.to_fen()).unwrap();
+        assert_eq!(game_arr[..], round_tripped.to_game_arr()[..]);
+    }
+}
@@ -1,14 +1,12 @@
 extern crate legal_chess;
 
-use legal_chess::{
-    chessmove, game,
-    pieces::{piece, position},
-};
+use legal_chess::{chessmove, game};
 use std::collections::HashMap;
 
 #[derive(Debug, PartialEq)]
 pub struct Counter(pub u128);
 
+#[allow(clippy::clone_on_copy)]
 pub fn perft(
     game: &mut game::Game,
     depth: u8,
@@ -25,10 +23,10 @@ pub fn perft(
         return 1;
     }
 
-    let moves = game.legal_moves();
-
     if depth == 1 {
-        for mv in &moves {
+        let moves = game.legal_moves_detailed();
+        for info in &moves {
+            let mv = &info.chessmove;
             let mut move_sequence = vec![];
 
             for past_move in move_stack.iter() {
@@ -52,35 +50,12 @@ pub fn perft(
                 }
             };
 
-            if game
-                .board()
-                .get_square(position::Position((mv.to).0, (mv.to).1))
-                .is_some()
-            {
-                capture_counter.0 += 1;
-            }
-
-            let piece = match game
-                .board()
-                .get_square(position::Position((mv.from).0, (mv.from).1))
-            {
-                None => panic!(),
-                Some(v) => v,
-            };
-
-            if piece.piece() == piece::PieceEnum::KING
-                && ((mv.from).0 as i8 - (mv.to).0 as i8).abs() == 2
-            {
+            if info.is_castle() {
                 castle_counter.0 += 1;
-            } else if piece.piece() == piece::PieceEnum::PAWN {
-                if (mv.from).0 != (mv.to).0
-                    && game
-                        .board()
-                        .get_square(position::Position((mv.to).0, (mv.to).1))
-                        .is_none()
-                {
-                    ep_counter.0 += 1;
-                }
+            } else if info.kind == chessmove::MoveKind::EnPassant {
+                ep_counter.0 += 1;
+            } else if info.is_capture() {
+                capture_counter.0 += 1;
             }
         }
         return moves.len();
@@ -88,7 +63,7 @@ pub fn perft(
 
     let mut nodes = 0;
 
-    for mv in moves {
+    for mv in game.legal_moves() {
         move_stack.push(mv);
         game.make_move(mv);
 
@@ -0,0 +1,176 @@
+#![cfg(feature = "serde")]
+extern crate legal_chess;
+extern crate serde_json;
+
+use legal_chess::pieces::{piece, position};
+use legal_chess::{board, chessmove, color, fen, game, serialization, square};
+use serde::{Deserialize, Serialize};
+
+// the positions of the perft tests
+const PERFT_POSITIONS: [&str; 6] = [
+    fen::INITIAL_FEN,
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+];
+
+#[derive(Serialize, Deserialize)]
+struct Structured {
+    #[serde(with = "serialization::structured_game")]
+    game: game::Game,
+    #[serde(with = "serialization::structured_board")]
+    board: board::Board,
+}
+
+fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
+    serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+}
+
+// the positions after every legal move, which adds en passant squares and lost castling rights
+fn positions() -> Vec<game::Game> {
+    let mut positions = vec![];
+    for fen in PERFT_POSITIONS.iter() {
+        let mut game = game::Game::from_fen(fen).unwrap();
+        positions.push(game.clone());
+        for mv in game.legal_moves() {
+            game.make_move(mv);
+            positions.push(game.clone());
+            game.undo_last_move();
+        }
+    }
+    positions
+}
+
+#[test]
+fn games_and_boards_round_trip() {
+    for game in positions() {
+        assert_eq!(game.to_fen(), round_trip(&game).to_fen());
+        assert_eq!(*game.board(), round_trip(game.board()));
+
+        let structured = round_trip(&Structured {
+            game: game.clone(),
+            board: *game.board(),
+        });
+        assert_eq!(game.to_fen(), structured.game.to_fen());
+        assert_eq!(*game.board(), structured.board);
+    }
+}
+
+#[test]
+fn moves_round_trip() {
+    for game in positions() {
+        for mv in game.legal_moves() {
+            assert_eq!(mv, round_trip(&mv));
+        }
+    }
+}
+
+#[test]
+fn serialised_forms() {
+    let mut game = game::Game::new();
+    let mv = game.parse_san("e4").unwrap();
+    game.make_move(mv);
+
+    assert_eq!("\"e2e4\"", serde_json::to_string(&mv).unwrap());
+    assert_eq!(
+        "\"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1\"",
+        serde_json::to_string(&game).unwrap()
+    );
+    assert_eq!(
+        "\"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR\"",
+        serde_json::to_string(game.board()).unwrap()
+    );
+    assert_eq!(
+        "\"BLACK\"",
+        serde_json::to_string(&color::Color::BLACK).unwrap()
+    );
+    assert_eq!(
+        "\"KNIGHT\"",
+        serde_json::to_string(&piece::PieceEnum::KNIGHT).unwrap()
+    );
+    assert_eq!(
+        "\"Queen\"",
+        serde_json::to_string(&piece::PromotionPiece::Queen).unwrap()
+    );
+    assert_eq!(
+        "[5,4]",
+        serde_json::to_string(&position::Position(5, 4)).unwrap()
+    );
+    let e3: square::Square = "e3".parse().unwrap();
+    assert_eq!("\"e3\"", serde_json::to_string(&e3).unwrap());
+
+    let structured = serde_json::to_value(Structured {
+        game: game::Game::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 3 40").unwrap(),
+        board: board::Board::initial(),
+    })
+    .unwrap();
+    assert_eq!(
+        serde_json::json!({
+            "pieces": {
+                "e1": {"piece": "KING", "color": "WHITE"},
+                "h1": {"piece": "ROOK", "color": "WHITE"},
+                "e8": {"piece": "KING", "color": "BLACK"},
+            },
+            "side_to_move": "WHITE",
+            "castling_rights_white": [true, false],
+            "castling_rights_black": [false, false],
+            "en_passant": null,
+            "half_moves": 3,
+            "full_moves": 40,
+        }),
+        structured["game"]
+    );
+    assert_eq!(32, structured["board"].as_object().unwrap().len());
+}
+
+#[test]
+fn invalid_positions_are_errors() {
+    let game = |json: &str| serde_json::from_str::<game::Game>(json).is_err();
+    assert!(game(
+        "\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w KQkq - 0 1\""
+    ));
+    assert!(game(
+        "\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1\""
+    ));
+    assert!(game("\"4k3/4R3/8/8/8/8/8/4K3 w - - 0 1\""));
+    assert!(game("5"));
+
+    assert!(serde_json::from_str::<board::Board>("\"8/8/8/8/8/8/8/8\"").is_err());
+    assert!(serde_json::from_str::<chessmove::ChessMove>("\"e2e9\"").is_err());
+    assert!(serde_json::from_str::<square::Square>("\"i1\"").is_err());
+
+    let structured = |game: serde_json::Value| {
+        serde_json::from_value::<Structured>(serde_json::json!({
+            "game": game,
+            "board": serde_json::to_value(Structured {
+                game: game::Game::new(),
+                board: board::Board::initial(),
+            })
+            .unwrap()["board"],
+        }))
+    };
+    let mut valid = serde_json::json!({
+        "pieces": {
+            "e1": {"piece": "KING", "color": "WHITE"},
+            "e8": {"piece": "KING", "color": "BLACK"},
+        },
+        "side_to_move": "BLACK",
+        "castling_rights_white": [false, false],
+        "castling_rights_black": [false, false],
+        "en_passant": null,
+        "half_moves": 0,
+        "full_moves": 1,
+    });
+    assert!(structured(valid.clone()).is_ok());
+    valid["castling_rights_white"] = serde_json::json!([true, false]);
+    assert!(structured(valid.clone()).is_err());
+    valid["castling_rights_white"] = serde_json::json!([false, false]);
+    valid["pieces"]["e7"] = serde_json::json!({"piece": "ROOK", "color": "BLACK"});
+    assert!(structured(valid.clone()).is_err());
+    valid["side_to_move"] = serde_json::json!("WHITE");
+    assert!(structured(valid.clone()).is_ok());
+    valid["pieces"]["a1"] = serde_json::json!({"piece": "PAWN", "color": "WHITE"});
+    assert!(structured(valid).is_err());
+}